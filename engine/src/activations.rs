@@ -29,6 +29,10 @@ impl OutputNeuron for Sigmoid {
     fn value(&self) -> f32 {
         self.value
     }
+
+    fn name(&self) -> Option<&str> {
+        Some(&self._type)
+    }
 }
 
 impl Sigmoid {
@@ -1,9 +1,12 @@
 pub mod activations;
 pub mod nn;
+pub mod rng;
+pub mod selection;
 
 pub use typetag;
 
 pub trait NeuronInfo {
     fn _type(&self) -> &'static str;
     fn id(&self) -> usize;
+    fn set_id(&mut self, id: usize);
 }
@@ -1,19 +1,28 @@
-use std::fmt::Debug;
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    sync::atomic::{AtomicUsize, Ordering},
+};
 
 use dyn_clone::{clone_trait_object, DynClone};
 use hashbrown::HashSet;
 use indexmap::IndexMap;
 use log::debug;
 use macros::{DNeuronInfo, SubTraits};
-use rand::{random, seq::IteratorRandom, Rng};
+use rand::{seq::IteratorRandom, Rng};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
-use crate::{nn::util::connection_pair_exists, NeuronInfo};
+use crate::{
+    nn::util::connection_pair_exists,
+    rng::{random, thread_rng},
+    NeuronInfo,
+};
 
 use self::mutate::MutationSelector;
 
 pub mod mutate;
+pub mod provenance;
 pub mod reproduce;
 pub mod util;
 
@@ -44,19 +53,47 @@ impl NeuronInfo for Node {
             Node::Neuron(n) => n.id(),
         }
     }
+
+    fn set_id(&mut self, id: usize) {
+        match self {
+            Node::None => unreachable!(),
+            Node::Input(n) => n.set_id(id),
+            Node::Output(n) => n.set_id(id),
+            Node::Neuron(n) => n.set_id(id),
+        }
+    }
+}
+
+/// Ids handed out to new neurons and edges, 0 is left for genes made without one
+static NEXT_INNOVATION: AtomicUsize = AtomicUsize::new(1);
+
+/// Unique id for a new gene. Genes keep their id through crossover, which is how the same
+/// gene is found in both parents
+pub fn next_innovation() -> usize {
+    NEXT_INNOVATION.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Keeps later ids clear of `id`, for genes that weren't made by this process
+pub fn reserve_innovation(id: usize) {
+    NEXT_INNOVATION.fetch_max(id + 1, Ordering::Relaxed);
 }
 
 #[derive(Debug, Default, Serialize, Deserialize, PartialEq, Clone, Copy)]
 pub struct Edge {
     pub weight: f32,
     pub enabled: bool,
+    /// 0 for edges without one, those match by the genes they connect instead
+    #[serde(default)]
+    pub innovation: usize,
 }
 
 impl Edge {
+    /// A random weight without an innovation id, so it matches by the genes it connects
     pub fn random() -> Edge {
         Edge {
             weight: random(),
             enabled: random(),
+            innovation: 0,
         }
     }
 }
@@ -77,6 +114,11 @@ pub trait OutputNeuron: NeuronSubTraits {
     fn step(&self, edge: &Edge, input: f32) -> f32;
     fn finish_and_save(&mut self, partial: f32) -> f32;
     fn value(&self) -> f32;
+
+    /// What the output controls, if the neuron was given a name
+    fn name(&self) -> Option<&str> {
+        None
+    }
 }
 
 clone_trait_object!(Neuron);
@@ -84,9 +126,22 @@ clone_trait_object!(Neuron);
 pub trait Neuron: NeuronSubTraits {
     fn step(&self, edge: &Edge, input: f32) -> f32;
     fn finish(&self, partial: f32) -> f32;
+
+    /// Constant added to the summed input, None if the neuron has none
+    fn bias(&self) -> Option<f32> {
+        None
+    }
+
+    fn set_bias(&mut self, _bias: f32) {}
 }
 
 pub type GraphSize = u16;
+
+/// Written in front of saved nets, files without it predate versioning
+const NET_MAGIC: &[u8; 4] = b"NNET";
+/// Bumped whenever the saved layout of `Net` changes
+pub const NET_FORMAT_VERSION: u32 = 2;
+
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct NeuralGraph {
     pub layers: Vec<Vec<GraphNode>>,
@@ -145,6 +200,14 @@ pub enum NeuralGraphError {
     },
 }
 
+#[derive(Error, Debug)]
+pub enum NetFileError {
+    #[error("Could not read net: {0}")]
+    Bincode(#[from] bincode::Error),
+    #[error("Net was saved in format {0}, newer than this build reads")]
+    Version(u32),
+}
+
 impl NeuralGraph {
     pub fn new() -> NeuralGraph {
         NeuralGraph { layers: Vec::new() }
@@ -242,16 +305,22 @@ impl NeuralGraph {
         let node = &mut self.layers[from.layer as usize][from.node as usize];
         let before = node.connections.len();
         node.connections.retain(|x| !x.to.eq(to));
-        node.connections.len() == before
+        node.connections.len() != before
     }
 
     pub fn removed_node(&mut self, remove: GraphLocation) {
         self.layers[remove.layer as usize].remove(remove.node as usize);
 
         self.layers.iter_mut().for_each(|layer| {
-            layer
-                .iter_mut()
-                .for_each(|node| node.connections.retain(|c| c.to.ne(&remove)))
+            layer.iter_mut().for_each(|node| {
+                node.connections.retain(|c| c.to.ne(&remove));
+                // later nodes of the layer moved up by one
+                for c in node.connections.iter_mut() {
+                    if c.to.layer == remove.layer && c.to.node > remove.node {
+                        c.to.node -= 1;
+                    }
+                }
+            })
         })
     }
 
@@ -334,11 +403,15 @@ impl NeuralGraph {
         }
 
         let subtract_from_end = subtract_from_end.unwrap_or_default();
-        let mut rng = rand::thread_rng();
+        if from as usize >= self.layers.len().saturating_sub(subtract_from_end) {
+            return None;
+        }
+
+        let mut rng = thread_rng();
         let layer_idx = rng.gen_range(from as usize..self.layers.len() - subtract_from_end);
         let layer = &self.layers[layer_idx];
 
-        if layer.len() == 0 {
+        if layer.len() <= subtract_from_end {
             return None;
         }
         Some(GraphLocation::new(
@@ -377,7 +450,7 @@ impl NeuralGraph {
                         )
                     })
             })
-            .choose_stable(&mut rand::thread_rng())
+            .choose_stable(&mut thread_rng())
     }
 
     pub fn has_cycle(&self, start_from: Option<GraphLocation>) -> bool {
@@ -387,22 +460,47 @@ impl NeuralGraph {
 
         let start = start_from.unwrap_or(GraphLocation::default());
 
-        let mut visited = HashSet::new();
-        let mut stack = Vec::new();
-        stack.push(start);
-
-        while let Some(loc) = stack.pop() {
-            if visited.contains(&loc) {
-                return true;
-            }
-            visited.insert(loc);
-
-            for node in &self.layers[loc.layer as usize][loc.node as usize].connections {
-                stack.push(node.to.clone());
+        // only nodes still on the current path close a cycle, reaching a finished node
+        // again through another path is fine
+        let mut done = HashSet::new();
+        let mut path = HashSet::new();
+        let mut stack = vec![(start, 0)];
+        path.insert(start);
+
+        while let Some((loc, next)) = stack.last_mut() {
+            let connections = &self.layers[loc.layer as usize][loc.node as usize].connections;
+            match connections.get(*next) {
+                Some(c) => {
+                    *next += 1;
+                    if path.contains(&c.to) {
+                        return true;
+                    }
+                    if !done.contains(&c.to) {
+                        path.insert(c.to);
+                        stack.push((c.to, 0));
+                    }
+                }
+                None => {
+                    path.remove(loc);
+                    done.insert(*loc);
+                    stack.pop();
+                }
             }
         }
         false
     }
+
+    pub fn num_nodes(&self) -> usize {
+        self.layers.iter().map(|layer| layer.len()).sum()
+    }
+
+    pub fn num_edges(&self) -> usize {
+        self.layers
+            .iter()
+            .flatten()
+            .map(|node| node.connections.len())
+            .sum()
+    }
 }
 
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
@@ -410,6 +508,70 @@ pub struct Net {
     pub graph: NeuralGraph,
     pub input_layer: GraphSize,
     pub output_layer: GraphSize,
+    /// Mutations and inheritance that produced this net, only recorded once enabled.
+    /// bincode ignores `serde(default)`, save with `to_bytes` to keep older files loading
+    #[serde(default)]
+    pub provenance: Option<Vec<provenance::ProvenanceEvent>>,
+}
+
+/// Edges before innovation ids were added, format 1 and unversioned files
+#[derive(Deserialize)]
+struct EdgeV1 {
+    to: GraphLocation,
+    weight: f32,
+    enabled: bool,
+}
+
+#[derive(Deserialize)]
+struct GraphNodeV1 {
+    value: Node,
+    connections: Vec<EdgeV1>,
+}
+
+impl From<Vec<Vec<GraphNodeV1>>> for NeuralGraph {
+    fn from(layers: Vec<Vec<GraphNodeV1>>) -> Self {
+        let layers = layers
+            .into_iter()
+            .map(|layer| {
+                layer
+                    .into_iter()
+                    .map(|node| GraphNode {
+                        value: node.value,
+                        connections: node
+                            .connections
+                            .into_iter()
+                            .map(|e| GraphEdge {
+                                to: e.to,
+                                value: Edge {
+                                    weight: e.weight,
+                                    enabled: e.enabled,
+                                    innovation: 0,
+                                },
+                            })
+                            .collect(),
+                    })
+                    .collect()
+            })
+            .collect();
+        NeuralGraph { layers }
+    }
+}
+
+/// Format 1, before innovation ids
+#[derive(Deserialize)]
+struct NetV1 {
+    layers: Vec<Vec<GraphNodeV1>>,
+    input_layer: GraphSize,
+    output_layer: GraphSize,
+    provenance: Option<Vec<provenance::ProvenanceEvent>>,
+}
+
+/// Layout of nets saved before provenance and format versions were added
+#[derive(Deserialize)]
+struct LegacyNet {
+    layers: Vec<Vec<GraphNodeV1>>,
+    input_layer: GraphSize,
+    output_layer: GraphSize,
 }
 
 #[derive(Debug, Default, Serialize, Deserialize, Clone, DNeuronInfo, SubTraits)]
@@ -427,9 +589,72 @@ impl Neuron for BasicNeuron {
     fn finish(&self, partial: f32) -> f32 {
         partial + self.bias
     }
+
+    fn bias(&self) -> Option<f32> {
+        Some(self.bias)
+    }
+
+    fn set_bias(&mut self, bias: f32) {
+        self.bias = bias;
+    }
+}
+
+impl BasicNeuron {
+    pub fn new(bias: f32, id: usize) -> BasicNeuron {
+        BasicNeuron { bias, id }
+    }
 }
 
 impl Net {
+    /// bincode behind `NET_MAGIC` and `NET_FORMAT_VERSION`
+    pub fn to_bytes(&self) -> Result<Vec<u8>, NetFileError> {
+        let mut bytes = NET_MAGIC.to_vec();
+        bytes.extend(bincode::serialize(&(NET_FORMAT_VERSION, self))?);
+        Ok(bytes)
+    }
+
+    /// Reads nets saved by `to_bytes` and the unversioned ones saved before it
+    pub fn from_bytes(bytes: &[u8]) -> Result<Net, NetFileError> {
+        let net = match bytes.strip_prefix(NET_MAGIC) {
+            None => {
+                let legacy: LegacyNet = bincode::deserialize(bytes)?;
+                Net {
+                    graph: legacy.layers.into(),
+                    input_layer: legacy.input_layer,
+                    output_layer: legacy.output_layer,
+                    provenance: None,
+                }
+            }
+            Some(versioned) => match bincode::deserialize::<u32>(versioned)? {
+                1 => {
+                    let (_, net): (u32, NetV1) = bincode::deserialize(versioned)?;
+                    Net {
+                        graph: net.layers.into(),
+                        input_layer: net.input_layer,
+                        output_layer: net.output_layer,
+                        provenance: net.provenance,
+                    }
+                }
+                NET_FORMAT_VERSION => bincode::deserialize::<(u32, Net)>(versioned)?.1,
+                version => return Err(NetFileError::Version(version)),
+            },
+        };
+        net.reserve_innovations();
+        Ok(net)
+    }
+
+    /// Keeps new genes from reusing the ids of this net's neurons and edges
+    pub fn reserve_innovations(&self) {
+        for node in self.graph.layers.iter().flatten() {
+            if !matches!(node.value, Node::None) {
+                reserve_innovation(node.value.id());
+            }
+            for c in node.connections.iter() {
+                reserve_innovation(c.value.innovation);
+            }
+        }
+    }
+
     pub fn from_preserving_basic(from: &Net) -> Result<Net, NeuralGraphError> {
         let mut g = NeuralGraph::new();
         g.add_layer_to_end();
@@ -444,11 +669,31 @@ impl Net {
             graph: g,
             input_layer: 0,
             output_layer: 1,
+            provenance: from.provenance.as_ref().map(|_| Vec::new()),
         })
     }
 
+    pub fn enable_provenance(&mut self) {
+        if self.provenance.is_none() {
+            self.provenance = Some(Vec::new());
+        }
+    }
+
+    pub fn record(&mut self, event: provenance::ProvenanceEvent) {
+        if let Some(p) = self.provenance.as_mut() {
+            p.push(event);
+        }
+    }
+
+    /// Copies the structure of `from`, keeping this net's provenance
+    pub fn copy_structure(&mut self, from: &Net) {
+        self.graph = from.graph.clone();
+        self.input_layer = from.input_layer;
+        self.output_layer = from.output_layer;
+    }
+
     pub fn gen(input_nodes: &[Node], output_nodes: &[Node]) -> Result<Net, NeuralGraphError> {
-        let mut rng = rand::thread_rng();
+        let mut rng = thread_rng();
         let mut g = NeuralGraph::new();
         let input_layer = g.add_layer_to_end();
         for value in input_nodes {
@@ -457,7 +702,6 @@ impl Net {
 
         let mut num_internal_layers = rng.gen_range(0..5);
         let mut remove_layers = 0;
-        let mut id = 0;
         for _ in 0..num_internal_layers {
             let num_nodes = rng.gen_range(0..10);
             if num_nodes == 0 {
@@ -466,11 +710,11 @@ impl Net {
             }
             let l = g.add_layer_to_end();
             for _ in 0..num_nodes {
-                g.add_node(
-                    l,
-                    GraphNode::new(Node::Neuron(Box::new(BasicNeuron { bias: random(), id }))),
-                )?;
-                id += 1;
+                let neuron = BasicNeuron {
+                    bias: random(),
+                    id: next_innovation(),
+                };
+                g.add_node(l, GraphNode::new(Node::Neuron(Box::new(neuron))))?;
             }
         }
         num_internal_layers -= remove_layers;
@@ -529,6 +773,7 @@ impl Net {
             graph: g,
             input_layer,
             output_layer,
+            provenance: None,
         })
     }
 
@@ -551,6 +796,29 @@ impl Net {
     }
 
     pub fn tick(&mut self) {
+        self.propagate(None);
+    }
+
+    /// What every node output during a tick with the current inputs, hidden neurons no
+    /// enabled edge reaches are left out
+    pub fn activations(&self) -> HashMap<GraphLocation, f32> {
+        let mut net = self.clone();
+        let mut activations = HashMap::new();
+        net.propagate(Some(&mut activations));
+        for (l, layer) in net.graph.layers.iter().enumerate() {
+            for (n, node) in layer.iter().enumerate() {
+                let value = match &node.value {
+                    Node::Input(i) => i.as_standard(),
+                    Node::Output(o) => o.value(),
+                    _ => continue,
+                };
+                activations.insert(GraphLocation::new(l as GraphSize, n as GraphSize), value);
+            }
+        }
+        activations
+    }
+
+    fn propagate(&mut self, mut record: Option<&mut HashMap<GraphLocation, f32>>) {
         let mut next_layer_inputs: IndexMap<GraphSize, Vec<(GraphEdge, f32)>> = IndexMap::new();
         let input_layer = self.graph.layers.first().unwrap();
         for node in input_layer.iter() {
@@ -595,14 +863,17 @@ impl Net {
                 *got_layer
             };
 
-            for (node, partial) in partials {
-                let node = self.graph.get_node_mut(&node).unwrap();
+            for (location, partial) in partials {
+                let node = self.graph.get_node_mut(&location).unwrap();
                 match &mut node.value {
                     Node::Output(o) => {
                         o.finish_and_save(partial);
                     }
                     Node::Neuron(n) => {
                         let step_value = n.finish(partial);
+                        if let Some(record) = record.as_deref_mut() {
+                            record.insert(location, step_value);
+                        }
                         for c in node.connections.iter() {
                             if !c.value.enabled {
                                 continue;
@@ -627,14 +898,18 @@ impl Net {
     pub fn reproduce(
         a: &Net,
         b: &Net,
+        fitness: reproduce::ParentFitness,
         _types: &[impl reproduce::Reproducer],
         mut generator: impl reproduce::Generator,
     ) -> Result<Net, reproduce::ReproduceError> {
         let mut net = Net::from_preserving_basic(&a)?;
+        if b.provenance.is_some() {
+            net.enable_provenance();
+        }
         loop {
             let (idx, done) = generator.generate(&a.graph, &b.graph, _types)?;
 
-            _types[idx].reproduce(&a, &b, &mut net)?;
+            _types[idx].reproduce(a, b, &fitness, &mut net)?;
 
             if done {
                 break;
@@ -655,11 +930,19 @@ impl Net {
             let (is_link, idx, done) =
                 generator.generate(&self.graph, link_mutators, &neuron_mutators)?;
 
-            if is_link {
-                link_mutators[idx].mutate(self)?;
+            let result = if is_link {
+                link_mutators[idx].mutate(self)
             } else {
-                neuron_mutators[idx].mutate(self, neurons, neuron_selector)?;
-            }
+                neuron_mutators[idx].mutate(self, neurons, neuron_selector)
+            };
+            let success = match result {
+                Ok(success) => success,
+                Err(e) => {
+                    generator.reset();
+                    return Err(e);
+                }
+            };
+            generator.record(is_link, idx, success);
 
             if done {
                 break;
@@ -670,7 +953,7 @@ impl Net {
 }
 
 #[cfg(test)]
-mod test_requirements {
+pub(crate) mod test_requirements {
     use macros::{DNeuronInfo, SubTraits};
     use serde::{Deserialize, Serialize};
 
@@ -769,9 +1052,14 @@ mod test_requirements {
 
 #[cfg(test)]
 mod test {
-    use crate::{activations::Sigmoid, nn::Edge};
+    use crate::{
+        activations::Sigmoid,
+        nn::{BasicNeuron, Edge},
+    };
 
-    use super::{test_requirements::*, GraphLocation, GraphNode, Node};
+    use super::{
+        test_requirements::*, GraphLocation, GraphNode, GraphSize, Net, NeuralGraph, Node,
+    };
 
     #[test]
     #[rustfmt::skip]
@@ -812,4 +1100,173 @@ mod test {
 
         assert!(g.has_cycle(Some(GraphLocation::new(0, 1))));
     }
+
+    #[test]
+    #[rustfmt::skip]
+    fn diamond_is_not_a_cycle() {
+        let input_nodes = [Node::Input(Box::new(BlankInput::new(0.0, 0)))];
+        let output_nodes = [Node::Output(Sigmoid::new(0.0, 1, "a".to_owned()))];
+
+        let mut g = create_graph(&input_nodes, &output_nodes);
+        g.add_layer(1);
+        g.add_node(1, GraphNode::blank()).unwrap();
+        g.add_node(1, GraphNode::blank()).unwrap();
+
+        g.add_edge(GraphLocation::new(0, 0), GraphLocation::new(1, 0), Edge::default()).unwrap();
+        g.add_edge(GraphLocation::new(0, 0), GraphLocation::new(1, 1), Edge::default()).unwrap();
+        g.add_edge(GraphLocation::new(1, 0), GraphLocation::new(2, 0), Edge::default()).unwrap();
+        g.add_edge(GraphLocation::new(1, 1), GraphLocation::new(2, 0), Edge::default()).unwrap();
+
+        assert!(!g.has_cycle(Some(GraphLocation::new(0, 0))));
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn removed_node_shifts_edges() {
+        let input_nodes = [Node::Input(Box::new(BlankInput::new(0.0, 0)))];
+        let output_nodes = [Node::Output(Sigmoid::new(0.0, 1, "a".to_owned()))];
+
+        let mut g = create_graph(&input_nodes, &output_nodes);
+        g.add_layer(1);
+        g.add_node(1, GraphNode::blank()).unwrap();
+        g.add_node(1, GraphNode::blank()).unwrap();
+
+        g.add_edge(GraphLocation::new(0, 0), GraphLocation::new(1, 0), Edge::default()).unwrap();
+        g.add_edge(GraphLocation::new(0, 0), GraphLocation::new(1, 1), Edge::default()).unwrap();
+        g.removed_node(GraphLocation::new(1, 0));
+
+        let connections = &g.layers[0][0].connections;
+        assert_eq!(connections.len(), 1);
+        assert_eq!(connections[0].to, GraphLocation::new(1, 0));
+    }
+
+    #[test]
+    fn activations() {
+        let input_nodes = [Node::Input(Box::new(BlankInput::new(2.0, 0)))];
+        let output_nodes = [Node::Output(Sigmoid::new(0.0, 1, "a".to_owned()))];
+
+        let mut g = create_graph(&input_nodes, &output_nodes);
+        g.add_layer(1);
+        g.add_node(
+            1,
+            GraphNode::new(Node::Neuron(Box::new(TestNeuronA { value: 1.0, id: 2 }))),
+        )
+        .unwrap();
+        g.add_node(
+            1,
+            GraphNode::new(Node::Neuron(Box::new(TestNeuronA { value: 1.0, id: 3 }))),
+        )
+        .unwrap();
+        let edge = Edge {
+            weight: 0.5,
+            enabled: true,
+            ..Default::default()
+        };
+        g.add_edge(GraphLocation::new(0, 0), GraphLocation::new(1, 0), edge)
+            .unwrap();
+        g.add_edge(GraphLocation::new(1, 0), GraphLocation::new(2, 0), edge)
+            .unwrap();
+
+        let net = Net {
+            graph: g,
+            input_layer: 0,
+            output_layer: 2,
+            provenance: None,
+        };
+        let activations = net.activations();
+
+        assert_eq!(activations[&GraphLocation::new(0, 0)], 2.0);
+        assert_eq!(activations[&GraphLocation::new(1, 0)], 2.0);
+        // nothing reaches the second hidden neuron
+        assert!(!activations.contains_key(&GraphLocation::new(1, 1)));
+
+        let mut ticked = net.clone();
+        ticked.tick();
+        let output = match &ticked.graph.layers[2][0].value {
+            Node::Output(o) => o.value(),
+            _ => unreachable!(),
+        };
+        assert_eq!(activations[&GraphLocation::new(2, 0)], output);
+    }
+
+    fn single_edge_graph() -> NeuralGraph {
+        create_graph(
+            &[Node::Input(Box::new(BlankInput::new(0.0, 0)))],
+            &[Node::Output(Sigmoid::new(0.0, 1, "a".to_owned()))],
+        )
+    }
+
+    #[test]
+    fn remove_edge_reports_removal() {
+        let (from, to) = (GraphLocation::new(0, 0), GraphLocation::new(1, 0));
+        let mut g = single_edge_graph();
+        g.add_edge(from, to, Edge::default()).unwrap();
+
+        assert!(g.remove_edge(&from, &to));
+        assert!(!g.remove_edge(&from, &to));
+    }
+
+    #[test]
+    fn random_from_empty_ranges() {
+        assert_eq!(NeuralGraph::new().random_input_or_hidden(), None);
+
+        // no hidden layer used to panic on an empty range
+        let mut g = single_edge_graph();
+        assert_eq!(g.random_hidden(), None);
+        assert_eq!(g.random_output_or_hidden(Some(1)), None);
+
+        g.add_layer(1);
+        assert_eq!(g.random_hidden(), None);
+        g.add_node(
+            1,
+            GraphNode::new(Node::Neuron(Box::new(BasicNeuron::default()))),
+        )
+        .unwrap();
+        for _ in 0..10 {
+            g.random_hidden();
+        }
+    }
+
+    #[test]
+    fn versioned_bytes() {
+        let mut g = single_edge_graph();
+        g.add_edge(
+            GraphLocation::new(0, 0),
+            GraphLocation::new(1, 0),
+            Edge::default(),
+        )
+        .unwrap();
+        let mut net = Net {
+            graph: g,
+            input_layer: 0,
+            output_layer: 1,
+            provenance: None,
+        };
+        net.enable_provenance();
+
+        let read = Net::from_bytes(&net.to_bytes().unwrap()).unwrap();
+        assert_eq!(read.graph.num_edges(), 1);
+        assert!(read.provenance.is_some());
+
+        // saved with plain bincode before provenance and innovation ids existed
+        let layers = net
+            .graph
+            .layers
+            .iter()
+            .map(|layer| {
+                layer
+                    .iter()
+                    .map(|n| {
+                        let edges = n.connections.iter().map(|c| (c.to, c.value.weight, true));
+                        (n.value.clone(), edges.collect::<Vec<_>>())
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let legacy = bincode::serialize(&(layers, 0 as GraphSize, 1 as GraphSize)).unwrap();
+        let read = Net::from_bytes(&legacy).unwrap();
+        assert_eq!(read.graph.num_edges(), 1);
+        assert!(read.provenance.is_none());
+        assert_eq!(read.graph.layers[0][0].connections[0].value.innovation, 0);
+    }
 }
@@ -1,7 +1,12 @@
-use serde::{Deserialize, Serialize};
+use rand::Rng;
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize};
 use thiserror::Error;
 
-use super::{Edge, GraphEdge, GraphLocation, Net, NeuralGraph, NeuralGraphError, Neuron, Node};
+use super::{
+    next_innovation, provenance::ProvenanceEvent, Edge, GraphEdge, GraphLocation, Net, NeuralGraph,
+    NeuralGraphError, Neuron, Node,
+};
+use crate::rng::thread_rng;
 
 pub trait LinkMutator {
     fn mutate(&self, net: &mut Net) -> Result<bool, MutateError>;
@@ -25,6 +30,31 @@ pub trait Generator {
         link_mutators: &[Box<dyn LinkMutator>],
         neuron_mutators: &[Box<dyn NeuronMutator>],
     ) -> Result<(bool, usize, bool), GeneratorError>;
+
+    /// Called with the outcome of every mutation the generator picked
+    fn record(&mut self, _is_link: bool, _idx: usize, _success: bool) {}
+
+    /// Called when a mutation failed partway through a net, the next net starts a new count
+    fn reset(&mut self) {}
+}
+
+impl<G: Generator + ?Sized> Generator for &mut G {
+    fn generate(
+        &mut self,
+        g: &NeuralGraph,
+        link_mutators: &[Box<dyn LinkMutator>],
+        neuron_mutators: &[Box<dyn NeuronMutator>],
+    ) -> Result<(bool, usize, bool), GeneratorError> {
+        (**self).generate(g, link_mutators, neuron_mutators)
+    }
+
+    fn record(&mut self, is_link: bool, idx: usize, success: bool) {
+        (**self).record(is_link, idx, success)
+    }
+
+    fn reset(&mut self) {
+        (**self).reset()
+    }
 }
 
 impl From<GeneratorError> for MutateError {
@@ -34,7 +64,14 @@ impl From<GeneratorError> for MutateError {
 }
 
 #[derive(Debug, Error)]
-pub enum GeneratorError {}
+pub enum GeneratorError {
+    #[error("No mutators to choose from")]
+    NoMutators,
+    #[error("Expected {expected} weights, got {found}")]
+    WeightsMismatch { expected: usize, found: usize },
+    #[error("A fixed count must make at least one mutation")]
+    ZeroCount,
+}
 
 #[derive(Debug, Error)]
 pub enum MutateError {
@@ -68,17 +105,32 @@ impl LinkMutator for AddEdge {
 
         if let Some(e) = net.graph.get_edge_mut(&input, &output) {
             e.value.enabled = true;
+            net.record(ProvenanceEvent::EdgeEnabled {
+                from: input,
+                to: output,
+            });
             return Ok(true);
         }
 
         net.graph
-            .add_edge(input, output, Edge::random())
+            .add_edge(
+                input,
+                output,
+                Edge {
+                    innovation: next_innovation(),
+                    ..Edge::random()
+                },
+            )
             .map_err(|err| MutateError::AddLinkError(err))?;
         if net.graph.has_cycle(Some(input)) {
             net.graph.remove_edge(&input, &output);
             return Ok(false);
         }
 
+        net.record(ProvenanceEvent::EdgeAdded {
+            from: input,
+            to: output,
+        });
         Ok(true)
     }
 }
@@ -90,7 +142,11 @@ impl LinkMutator for RemoveEdge {
     fn mutate(&self, net: &mut Net) -> Result<bool, MutateError> {
         if let Some((pos, e)) = net.graph.random_edge_mut() {
             let to = e.to.clone();
-            return Ok(net.graph.remove_edge(&pos, &to));
+            let removed = net.graph.remove_edge(&pos, &to);
+            if removed {
+                net.record(ProvenanceEvent::EdgeRemoved { from: pos, to });
+            }
+            return Ok(removed);
         }
         Ok(false)
     }
@@ -100,7 +156,8 @@ impl LinkMutator for RemoveEdge {
 pub struct AddNeuron;
 
 impl AddNeuron {
-    fn run(
+    /// Replaces the edge from `from` with a new neuron and an edge on either side of it
+    pub fn run(
         &self,
         from: GraphLocation,
         link_to_split: GraphEdge,
@@ -113,19 +170,40 @@ impl AddNeuron {
         if link_to_split.to.layer == from.layer + 1 {
             net.graph.add_layer(from.layer + 1);
             to_layer_new = layer + 1;
+            if net.output_layer >= layer {
+                net.output_layer += 1;
+            }
         }
 
+        let (_type, id) = (neuron._type().to_owned(), neuron.id());
         let new_node_pos = net.graph.push_node_at(layer, Node::Neuron(neuron));
+        // both halves are new genes, the first keeps the split edge's weight
         net.graph
-            .add_edge(from, new_node_pos, link_to_split.value)
+            .add_edge(
+                from,
+                new_node_pos,
+                Edge {
+                    innovation: next_innovation(),
+                    ..link_to_split.value
+                },
+            )
             .map_err(|err| MutateError::AddNeuronError(err))?;
         net.graph
             .add_edge(
                 new_node_pos,
                 GraphLocation::new(to_layer_new, link_to_split.to.node),
-                Edge::default(),
+                Edge {
+                    innovation: next_innovation(),
+                    ..Default::default()
+                },
             )
             .map_err(|err| MutateError::AddNeuronError(err))?;
+        net.record(ProvenanceEvent::NeuronAdded {
+            at: new_node_pos,
+            split: (from, link_to_split.to),
+            _type,
+            id,
+        });
         Ok(())
     }
 }
@@ -147,7 +225,10 @@ impl NeuronMutator for AddNeuron {
             return Err(MutateError::NeuronSelectorOutOfRange(neurons.len()));
         }
 
-        self.run(from, link_to_split.clone(), net, neurons[neuron].clone())?;
+        // every added neuron is a new gene, even when made from the same template
+        let mut neuron = neurons[neuron].clone();
+        neuron.set_id(next_innovation());
+        self.run(from, *link_to_split, net, neuron)?;
 
         Ok(true)
     }
@@ -170,6 +251,7 @@ impl NeuronMutator for RemoveNeuron {
         match net.graph.random_hidden() {
             Some(n) => {
                 net.graph.removed_node(n);
+                net.record(ProvenanceEvent::NeuronRemoved { at: n });
                 Ok(true)
             }
             None => Ok(false),
@@ -177,6 +259,391 @@ impl NeuronMutator for RemoveNeuron {
     }
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub enum MutationCount {
+    /// At least one, 0 fails to deserialize. Skip `Net::mutate` to leave a net unchanged
+    Fixed(#[serde(deserialize_with = "at_least_one")] usize),
+    /// Poisson distributed with the given mean, at least one mutation is always made
+    Poisson(f32),
+}
+
+impl Default for MutationCount {
+    fn default() -> Self {
+        MutationCount::Fixed(1)
+    }
+}
+
+impl MutationCount {
+    fn sample(&self) -> usize {
+        match self {
+            // only reachable by building `Fixed(0)` in code
+            MutationCount::Fixed(n) => (*n).max(1),
+            MutationCount::Poisson(lambda) => poisson(*lambda).max(1),
+        }
+    }
+}
+
+/// Rejects the 0 mutations a fixed count can't make
+fn at_least_one<'de, D: Deserializer<'de>>(deserializer: D) -> Result<usize, D::Error> {
+    match usize::deserialize(deserializer)? {
+        0 => Err(D::Error::custom(GeneratorError::ZeroCount)),
+        n => Ok(n),
+    }
+}
+
+/// Knuth's method, fine for the small means mutation counts use
+fn poisson(lambda: f32) -> usize {
+    let mut rng = thread_rng();
+    let limit = (-lambda.max(0.0)).exp();
+    let mut k = 0;
+    let mut p = rng.gen::<f32>();
+    while p > limit {
+        k += 1;
+        p *= rng.gen::<f32>();
+    }
+    k
+}
+
+/// Picks (is_link, index) with probability proportional to its weight
+fn choose_weighted(link_weights: &[f32], neuron_weights: &[f32]) -> Option<(bool, usize)> {
+    let weights = link_weights
+        .iter()
+        .enumerate()
+        .map(|(idx, w)| (true, idx, *w))
+        .chain(
+            neuron_weights
+                .iter()
+                .enumerate()
+                .map(|(idx, w)| (false, idx, *w)),
+        )
+        .filter(|(_, _, w)| *w > 0.0);
+    let total = weights.clone().map(|(_, _, w)| w).sum::<f32>();
+    if total <= 0.0 {
+        return None;
+    }
+
+    let mut pick = thread_rng().gen_range(0.0..total);
+    let mut last = None;
+    for (is_link, idx, w) in weights {
+        if pick < w {
+            return Some((is_link, idx));
+        }
+        pick -= w;
+        last = Some((is_link, idx));
+    }
+    last
+}
+
+fn check_weights(weights: &[f32], mutators: usize) -> Result<(), GeneratorError> {
+    if weights.len() != mutators {
+        return Err(GeneratorError::WeightsMismatch {
+            expected: mutators,
+            found: weights.len(),
+        });
+    }
+    Ok(())
+}
+
+/// Counts down the mutations left for the current net, sampling a new count when starting
+fn next_done(remaining: &mut Option<usize>, count: &MutationCount) -> bool {
+    let left = remaining.get_or_insert_with(|| count.sample());
+    *left -= 1;
+    if *left == 0 {
+        *remaining = None;
+        true
+    } else {
+        false
+    }
+}
+
+/// Chooses among all mutators with equal probability
+fn choose_uniform(
+    link_mutators: &[Box<dyn LinkMutator>],
+    neuron_mutators: &[Box<dyn NeuronMutator>],
+) -> Result<(bool, usize), GeneratorError> {
+    let total = link_mutators.len() + neuron_mutators.len();
+    if total == 0 {
+        return Err(GeneratorError::NoMutators);
+    }
+
+    let idx = thread_rng().gen_range(0..total);
+    if idx < link_mutators.len() {
+        Ok((true, idx))
+    } else {
+        Ok((false, idx - link_mutators.len()))
+    }
+}
+
+/// Makes exactly `count` mutations, each mutator equally likely.
+/// `count` is at least one, skip `Net::mutate` to leave a net unchanged
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FixedCount {
+    #[serde(deserialize_with = "at_least_one")]
+    count: usize,
+    #[serde(skip)]
+    remaining: Option<usize>,
+}
+
+impl FixedCount {
+    pub fn new(count: usize) -> Result<FixedCount, GeneratorError> {
+        if count == 0 {
+            return Err(GeneratorError::ZeroCount);
+        }
+        Ok(FixedCount {
+            count,
+            remaining: None,
+        })
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+}
+
+impl Generator for FixedCount {
+    fn generate(
+        &mut self,
+        _: &NeuralGraph,
+        link_mutators: &[Box<dyn LinkMutator>],
+        neuron_mutators: &[Box<dyn NeuronMutator>],
+    ) -> Result<(bool, usize, bool), GeneratorError> {
+        let (is_link, idx) = choose_uniform(link_mutators, neuron_mutators)?;
+        let done = next_done(&mut self.remaining, &MutationCount::Fixed(self.count));
+        Ok((is_link, idx, done))
+    }
+
+    fn reset(&mut self) {
+        self.remaining = None;
+    }
+}
+
+/// Makes a Poisson distributed number of mutations, each mutator equally likely
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PoissonCount {
+    pub lambda: f32,
+    #[serde(skip)]
+    remaining: Option<usize>,
+}
+
+impl PoissonCount {
+    pub fn new(lambda: f32) -> PoissonCount {
+        PoissonCount {
+            lambda,
+            remaining: None,
+        }
+    }
+}
+
+impl Generator for PoissonCount {
+    fn generate(
+        &mut self,
+        _: &NeuralGraph,
+        link_mutators: &[Box<dyn LinkMutator>],
+        neuron_mutators: &[Box<dyn NeuronMutator>],
+    ) -> Result<(bool, usize, bool), GeneratorError> {
+        let (is_link, idx) = choose_uniform(link_mutators, neuron_mutators)?;
+        let done = next_done(&mut self.remaining, &MutationCount::Poisson(self.lambda));
+        Ok((is_link, idx, done))
+    }
+
+    fn reset(&mut self) {
+        self.remaining = None;
+    }
+}
+
+/// Chooses mutators with probability proportional to their weight,
+/// weights are given in the same order as the mutators passed to `Net::mutate`
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct Weighted {
+    pub link_weights: Vec<f32>,
+    pub neuron_weights: Vec<f32>,
+    pub count: MutationCount,
+    #[serde(skip)]
+    remaining: Option<usize>,
+}
+
+impl Weighted {
+    pub fn new(link_weights: Vec<f32>, neuron_weights: Vec<f32>, count: MutationCount) -> Weighted {
+        Weighted {
+            link_weights,
+            neuron_weights,
+            count,
+            remaining: None,
+        }
+    }
+}
+
+impl Generator for Weighted {
+    fn generate(
+        &mut self,
+        _: &NeuralGraph,
+        link_mutators: &[Box<dyn LinkMutator>],
+        neuron_mutators: &[Box<dyn NeuronMutator>],
+    ) -> Result<(bool, usize, bool), GeneratorError> {
+        check_weights(&self.link_weights, link_mutators.len())?;
+        check_weights(&self.neuron_weights, neuron_mutators.len())?;
+        let (is_link, idx) = choose_weighted(&self.link_weights, &self.neuron_weights)
+            .ok_or(GeneratorError::NoMutators)?;
+        let done = next_done(&mut self.remaining, &self.count);
+        Ok((is_link, idx, done))
+    }
+
+    fn reset(&mut self) {
+        self.remaining = None;
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct MutatorStats {
+    pub attempts: f32,
+    pub successes: f32,
+}
+
+impl MutatorStats {
+    /// Success rate with a uniform prior, so untried mutators start at 0.5
+    pub fn rate(&self) -> f32 {
+        (self.successes + 1.0) / (self.attempts + 2.0)
+    }
+}
+
+/// Weights every mutator by how often it has succeeded so far,
+/// `decay` below 1.0 makes older outcomes count for less
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Adaptive {
+    pub count: MutationCount,
+    pub decay: f32,
+    /// Lowest weight any mutator can drop to, keeps every mutator reachable
+    pub min_rate: f32,
+    pub link_stats: Vec<MutatorStats>,
+    pub neuron_stats: Vec<MutatorStats>,
+    #[serde(skip)]
+    remaining: Option<usize>,
+}
+
+impl Default for Adaptive {
+    fn default() -> Self {
+        Adaptive::new(MutationCount::default(), 0.99, 0.05)
+    }
+}
+
+impl Adaptive {
+    pub fn new(count: MutationCount, decay: f32, min_rate: f32) -> Adaptive {
+        Adaptive {
+            count,
+            decay,
+            min_rate,
+            link_stats: Vec::new(),
+            neuron_stats: Vec::new(),
+            remaining: None,
+        }
+    }
+
+    fn weights(&self, stats: &[MutatorStats]) -> Vec<f32> {
+        stats.iter().map(|s| s.rate().max(self.min_rate)).collect()
+    }
+}
+
+impl Generator for Adaptive {
+    fn generate(
+        &mut self,
+        _: &NeuralGraph,
+        link_mutators: &[Box<dyn LinkMutator>],
+        neuron_mutators: &[Box<dyn NeuronMutator>],
+    ) -> Result<(bool, usize, bool), GeneratorError> {
+        self.link_stats
+            .resize(link_mutators.len(), MutatorStats::default());
+        self.neuron_stats
+            .resize(neuron_mutators.len(), MutatorStats::default());
+
+        let (is_link, idx) = choose_weighted(
+            &self.weights(&self.link_stats),
+            &self.weights(&self.neuron_stats),
+        )
+        .ok_or(GeneratorError::NoMutators)?;
+        let done = next_done(&mut self.remaining, &self.count);
+        Ok((is_link, idx, done))
+    }
+
+    fn reset(&mut self) {
+        self.remaining = None;
+    }
+
+    fn record(&mut self, is_link: bool, idx: usize, success: bool) {
+        for s in self
+            .link_stats
+            .iter_mut()
+            .chain(self.neuron_stats.iter_mut())
+        {
+            s.attempts *= self.decay;
+            s.successes *= self.decay;
+        }
+
+        let stats = if is_link {
+            &mut self.link_stats
+        } else {
+            &mut self.neuron_stats
+        };
+        if let Some(s) = stats.get_mut(idx) {
+            s.attempts += 1.0;
+            if success {
+                s.successes += 1.0;
+            }
+        }
+    }
+}
+
+/// Any of the ready made generators, lets experiment configs name the one to use
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub enum MutationGenerator {
+    FixedCount(FixedCount),
+    PoissonCount(PoissonCount),
+    Weighted(Weighted),
+    Adaptive(Adaptive),
+}
+
+impl Default for MutationGenerator {
+    fn default() -> Self {
+        MutationGenerator::FixedCount(FixedCount {
+            count: 1,
+            remaining: None,
+        })
+    }
+}
+
+impl Generator for MutationGenerator {
+    fn generate(
+        &mut self,
+        g: &NeuralGraph,
+        link_mutators: &[Box<dyn LinkMutator>],
+        neuron_mutators: &[Box<dyn NeuronMutator>],
+    ) -> Result<(bool, usize, bool), GeneratorError> {
+        match self {
+            MutationGenerator::FixedCount(m) => m.generate(g, link_mutators, neuron_mutators),
+            MutationGenerator::PoissonCount(m) => m.generate(g, link_mutators, neuron_mutators),
+            MutationGenerator::Weighted(m) => m.generate(g, link_mutators, neuron_mutators),
+            MutationGenerator::Adaptive(m) => m.generate(g, link_mutators, neuron_mutators),
+        }
+    }
+
+    fn record(&mut self, is_link: bool, idx: usize, success: bool) {
+        match self {
+            MutationGenerator::FixedCount(m) => m.record(is_link, idx, success),
+            MutationGenerator::PoissonCount(m) => m.record(is_link, idx, success),
+            MutationGenerator::Weighted(m) => m.record(is_link, idx, success),
+            MutationGenerator::Adaptive(m) => m.record(is_link, idx, success),
+        }
+    }
+
+    fn reset(&mut self) {
+        match self {
+            MutationGenerator::FixedCount(m) => m.reset(),
+            MutationGenerator::PoissonCount(m) => m.reset(),
+            MutationGenerator::Weighted(m) => m.reset(),
+            MutationGenerator::Adaptive(m) => m.reset(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use crate::{
@@ -187,7 +654,10 @@ mod test {
         },
     };
 
-    use super::AddNeuron;
+    use super::{
+        Adaptive, AddEdge, AddNeuron, FixedCount, Generator, LinkMutator, MutateError,
+        MutationCount, MutationGenerator, NeuronMutator, PoissonCount, RemoveEdge, Weighted,
+    };
 
     #[test]
     #[rustfmt::skip]
@@ -215,6 +685,7 @@ mod test {
         let e = Edge {
             weight: 1.3,
             enabled: true,
+            ..Default::default()
         };
         graph_verify.add_edge(GraphLocation::new(0, 0), GraphLocation::new(1, 0), e.clone()).unwrap();
         graph_verify.add_edge(GraphLocation::new(0, 1), GraphLocation::new(2, 0), Edge::default()).unwrap();
@@ -234,12 +705,14 @@ mod test {
             graph: graph_mutation,
             input_layer: 0,
             output_layer: 2,
+            ..Default::default()
         };
 
         let v = Net {
             graph: graph_verify,
             input_layer: 0,
-            output_layer: 3
+            output_layer: 3,
+            ..Default::default()
         };
 
         AddNeuron{}.run(GraphLocation::new(0, 0), GraphEdge {
@@ -247,6 +720,13 @@ mod test {
             value: e.clone(),
         }, &mut m, Box::new(BasicNeuron::default())).unwrap();
 
+        // both edges around the new neuron are new genes
+        let innovations = [(0, 0), (1, 0)].map(|(l, n)| m.graph.layers[l][n].connections[0].value.innovation);
+        assert!(innovations[0] != innovations[1] && !innovations.contains(&0));
+        for (l, n) in [(0, 0), (1, 0)] {
+            m.graph.layers[l][n].connections[0].value.innovation = 0;
+        }
+
         // let sim = Simulation {
         //     nets: vec![Nn { net: m, node_positions: vec![] }, Nn {net: v, node_positions: vec![]}],
         //     input_nodes: input_nodes.to_vec(),
@@ -256,5 +736,103 @@ mod test {
         // std::fs::write("mutate.bin", bincode::serialize(&sim).unwrap()).unwrap();
 
         assert_eq!(bincode::serialize(&m.graph).unwrap(), bincode::serialize(&v.graph).unwrap());
+        assert_eq!(m.output_layer, v.output_layer);
+    }
+
+    type Mutators = (Vec<Box<dyn LinkMutator>>, Vec<Box<dyn NeuronMutator>>);
+
+    fn mutators() -> Mutators {
+        (
+            vec![Box::new(AddEdge), Box::new(RemoveEdge)],
+            vec![Box::new(AddNeuron)],
+        )
+    }
+
+    fn run_to_done(generator: &mut impl Generator) -> Vec<(bool, usize)> {
+        let (links, neurons) = mutators();
+        let g = create_graph(&[], &[]);
+        let mut picked = Vec::new();
+        loop {
+            let (is_link, idx, done) = generator.generate(&g, &links, &neurons).unwrap();
+            picked.push((is_link, idx));
+            if done {
+                break;
+            }
+        }
+        picked
+    }
+
+    struct Failing;
+
+    impl LinkMutator for Failing {
+        fn mutate(&self, _: &mut Net) -> Result<bool, MutateError> {
+            Err(MutateError::NeuronSelectorOutOfRange(0))
+        }
+    }
+
+    #[test]
+    fn fixed_and_poisson_count() {
+        let mut fixed = FixedCount::new(3).unwrap();
+        assert_eq!(run_to_done(&mut fixed).len(), 3);
+        // the count restarts for the next net
+        assert_eq!(run_to_done(&mut fixed).len(), 3);
+        assert!(FixedCount::new(0).is_err());
+        let zero = bincode::serialize(&0usize).unwrap();
+        assert!(bincode::deserialize::<FixedCount>(&zero).is_err());
+        let one = bincode::serialize(&1usize).unwrap();
+        assert_eq!(bincode::deserialize::<FixedCount>(&one).unwrap().count(), 1);
+        let fixed_zero = bincode::serialize(&MutationCount::Fixed(0)).unwrap();
+        assert!(bincode::deserialize::<MutationCount>(&fixed_zero).is_err());
+
+        let mut poisson = PoissonCount::new(0.0);
+        assert_eq!(run_to_done(&mut poisson).len(), 1);
+        let mut poisson = PoissonCount::new(4.0);
+        assert!(!run_to_done(&mut poisson).is_empty());
+    }
+
+    #[test]
+    fn reset_after_error() {
+        let mut fixed = FixedCount::new(3).unwrap();
+        let links: Vec<Box<dyn LinkMutator>> = vec![Box::new(Failing)];
+        let mut net = Net::default();
+        assert!(net.mutate(&links, &[], &[], &|x| x, &mut fixed).is_err());
+        assert_eq!(fixed.remaining, None);
+        assert_eq!(run_to_done(&mut fixed).len(), 3);
+    }
+
+    #[test]
+    fn weighted() {
+        let mut weighted = Weighted::new(vec![0.0, 1.0], vec![0.0], MutationCount::Fixed(20));
+        assert!(run_to_done(&mut weighted).iter().all(|x| *x == (true, 1)));
+
+        let mut mismatched = Weighted::new(vec![1.0], vec![1.0], MutationCount::Fixed(1));
+        let (links, neurons) = mutators();
+        assert!(mismatched
+            .generate(&create_graph(&[], &[]), &links, &neurons)
+            .is_err());
+    }
+
+    #[test]
+    fn adaptive() {
+        let mut adaptive = Adaptive::new(MutationCount::Fixed(1), 1.0, 0.0);
+        run_to_done(&mut adaptive);
+        for _ in 0..50 {
+            adaptive.record(true, 0, true);
+            adaptive.record(true, 1, false);
+            adaptive.record(false, 0, false);
+        }
+        assert!(adaptive.link_stats[0].rate() > 0.9);
+        assert!(adaptive.link_stats[1].rate() < 0.1);
+
+        let mut generator = MutationGenerator::Adaptive(adaptive);
+        let picked = run_to_done(&mut generator);
+        assert_eq!(picked.len(), 1);
+
+        let bytes = bincode::serialize(&generator).unwrap();
+        let generator: MutationGenerator = bincode::deserialize(&bytes).unwrap();
+        match generator {
+            MutationGenerator::Adaptive(a) => assert_eq!(a.link_stats[0].successes, 50.0),
+            _ => unreachable!(),
+        }
     }
 }
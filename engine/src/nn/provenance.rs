@@ -0,0 +1,219 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+use super::GraphLocation;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum Parent {
+    A,
+    B,
+}
+
+/// What a mutator or reproducer did to a net, locations are as they were when the event happened
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum ProvenanceEvent {
+    EdgeAdded {
+        from: GraphLocation,
+        to: GraphLocation,
+    },
+    EdgeEnabled {
+        from: GraphLocation,
+        to: GraphLocation,
+    },
+    EdgeRemoved {
+        from: GraphLocation,
+        to: GraphLocation,
+    },
+    NeuronAdded {
+        at: GraphLocation,
+        split: (GraphLocation, GraphLocation),
+        _type: String,
+        id: usize,
+    },
+    NeuronRemoved {
+        at: GraphLocation,
+    },
+    /// The whole structure was copied from one parent
+    StructureInherited {
+        parent: Parent,
+    },
+    NodeInherited {
+        at: GraphLocation,
+        parent: Parent,
+    },
+    EdgeInherited {
+        from: GraphLocation,
+        to: GraphLocation,
+        parent: Parent,
+    },
+    EdgeAveraged {
+        from: GraphLocation,
+        to: GraphLocation,
+    },
+    LayerInherited {
+        layer: usize,
+        parent: Parent,
+    },
+    /// Changed by hand in an editor
+    Edited {
+        change: String,
+    },
+}
+
+fn loc(l: &GraphLocation) -> String {
+    format!("{},{}", l.layer, l.node)
+}
+
+impl Display for ProvenanceEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProvenanceEvent::EdgeAdded { from, to } => {
+                write!(f, "edge added {} -> {}", loc(from), loc(to))
+            }
+            ProvenanceEvent::EdgeEnabled { from, to } => {
+                write!(f, "edge enabled {} -> {}", loc(from), loc(to))
+            }
+            ProvenanceEvent::EdgeRemoved { from, to } => {
+                write!(f, "edge removed {} -> {}", loc(from), loc(to))
+            }
+            ProvenanceEvent::NeuronAdded {
+                at,
+                split,
+                _type,
+                id,
+            } => write!(
+                f,
+                "{_type} {id} inserted at {} splitting {} -> {}",
+                loc(at),
+                loc(&split.0),
+                loc(&split.1)
+            ),
+            ProvenanceEvent::NeuronRemoved { at } => write!(f, "neuron removed at {}", loc(at)),
+            ProvenanceEvent::StructureInherited { parent } => {
+                write!(f, "structure taken from parent {parent:?}")
+            }
+            ProvenanceEvent::NodeInherited { at, parent } => {
+                write!(f, "node {} taken from parent {parent:?}", loc(at))
+            }
+            ProvenanceEvent::EdgeInherited { from, to, parent } => write!(
+                f,
+                "edge {} -> {} taken from parent {parent:?}",
+                loc(from),
+                loc(to)
+            ),
+            ProvenanceEvent::EdgeAveraged { from, to } => {
+                write!(f, "edge {} -> {} averaged", loc(from), loc(to))
+            }
+            ProvenanceEvent::LayerInherited { layer, parent } => {
+                write!(f, "layer {layer} taken from parent {parent:?}")
+            }
+            ProvenanceEvent::Edited { change } => write!(f, "edited: {change}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        activations::Sigmoid,
+        nn::{
+            mutate::{AddNeuron, FixedCount, LinkMutator, NeuronMutator, RemoveEdge},
+            reproduce::{CloneParent, DefaultIterator, ParentFitness},
+            test_requirements::{create_graph, BlankInput},
+            BasicNeuron, Edge, GraphLocation, Net, Neuron, Node,
+        },
+        NeuronInfo,
+    };
+
+    use super::{Parent, ProvenanceEvent};
+
+    fn net() -> Net {
+        let input_nodes = [Node::Input(Box::new(BlankInput::new(0.0, 0)))];
+        let output_nodes = [Node::Output(Sigmoid::new(0.0, 1, "a".to_owned()))];
+        let mut graph = create_graph(&input_nodes, &output_nodes);
+        graph
+            .add_edge(
+                GraphLocation::new(0, 0),
+                GraphLocation::new(1, 0),
+                Edge::default(),
+            )
+            .unwrap();
+        Net {
+            graph,
+            input_layer: 0,
+            output_layer: 1,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn disabled_by_default() {
+        let mut n = net();
+        RemoveEdge.mutate(&mut n).unwrap();
+        assert!(n.provenance.is_none());
+    }
+
+    #[test]
+    fn records_mutations() {
+        let mut n = net();
+        n.enable_provenance();
+
+        let link_mutators: Vec<Box<dyn LinkMutator>> = vec![];
+        let neuron_mutators: Vec<Box<dyn NeuronMutator>> = vec![Box::new(AddNeuron)];
+        let neurons: Vec<Box<dyn Neuron>> = vec![Box::new(BasicNeuron::default())];
+        n.mutate(
+            &link_mutators,
+            &neuron_mutators,
+            &neurons,
+            &|_| 0,
+            FixedCount::new(1).unwrap(),
+        )
+        .unwrap();
+        RemoveEdge.mutate(&mut n).unwrap();
+
+        // the added neuron got a fresh innovation id
+        let id = n.graph.layers[1][0].value.id();
+        assert_ne!(id, 0);
+        let events = n.provenance.as_ref().unwrap();
+        assert_eq!(
+            events[0],
+            ProvenanceEvent::NeuronAdded {
+                at: GraphLocation::new(1, 0),
+                split: (GraphLocation::new(0, 0), GraphLocation::new(1, 0)),
+                _type: "BasicNeuron".to_owned(),
+                id,
+            }
+        );
+        assert_eq!(
+            events[0].to_string(),
+            format!("BasicNeuron {id} inserted at 1,0 splitting 0,0 -> 1,0")
+        );
+        assert!(matches!(events[1], ProvenanceEvent::EdgeRemoved { .. }));
+        assert_eq!(events.len(), 2);
+    }
+
+    #[test]
+    fn records_inheritance() {
+        let mut a = net();
+        a.enable_provenance();
+        a.record(ProvenanceEvent::NeuronRemoved {
+            at: GraphLocation::new(1, 0),
+        });
+        let b = net();
+
+        let child = Net::reproduce(
+            &a,
+            &b,
+            ParentFitness::new(0.0, 1.0),
+            &[CloneParent],
+            DefaultIterator::new(),
+        )
+        .unwrap();
+        // the parents history is not carried over, only how the child was made
+        assert_eq!(
+            child.provenance.unwrap(),
+            vec![ProvenanceEvent::StructureInherited { parent: Parent::B }]
+        );
+    }
+}
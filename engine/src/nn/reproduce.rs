@@ -1,15 +1,63 @@
-use rand::Rng;
+use rand::{seq::SliceRandom, Rng};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 use super::{
-    util::{self, ConnectionInfo},
-    Net, NeuralGraph, NeuralGraphError,
+    provenance::{Parent, ProvenanceEvent},
+    util::{self, find_gene, gene_id, matching_edge, ConnectionInfo},
+    Edge, GraphNode, GraphSize, Net, NeuralGraph, NeuralGraphError,
 };
-use crate::nn::GraphLocation;
+use crate::{nn::GraphLocation, rng::thread_rng};
 
 pub trait Reproducer {
-    fn reproduce(&self, a: &Net, b: &Net, output: &mut Net) -> Result<(), ReproduceError>;
+    fn reproduce(
+        &self,
+        a: &Net,
+        b: &Net,
+        fitness: &ParentFitness,
+        output: &mut Net,
+    ) -> Result<(), ReproduceError>;
+}
+
+impl Reproducer for Box<dyn Reproducer> {
+    fn reproduce(
+        &self,
+        a: &Net,
+        b: &Net,
+        fitness: &ParentFitness,
+        output: &mut Net,
+    ) -> Result<(), ReproduceError> {
+        (**self).reproduce(a, b, fitness, output)
+    }
+}
+
+/// Fitness of both parents, reproducers that are not fitness aware ignore it
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+pub struct ParentFitness {
+    pub a: f32,
+    pub b: f32,
+}
+
+impl ParentFitness {
+    pub fn new(a: f32, b: f32) -> ParentFitness {
+        ParentFitness { a, b }
+    }
+
+    /// Returns (fitter, other), ties go to A
+    pub fn order<'a>(&self, a: &'a Net, b: &'a Net) -> (&'a Net, &'a Net) {
+        match self.fitter() {
+            Parent::A => (a, b),
+            Parent::B => (b, a),
+        }
+    }
+
+    pub fn fitter(&self) -> Parent {
+        if self.a >= self.b {
+            Parent::A
+        } else {
+            Parent::B
+        }
+    }
 }
 
 #[derive(Debug, Error)]
@@ -56,31 +104,38 @@ struct NodeReplacement {
 
 impl Reproducer for Crossover {
     /// Crossover between two networks
-    fn reproduce(&self, a: &Net, b: &Net, output: &mut Net) -> Result<(), ReproduceError> {
+    fn reproduce(
+        &self,
+        a: &Net,
+        b: &Net,
+        _: &ParentFitness,
+        output: &mut Net,
+    ) -> Result<(), ReproduceError> {
         let common_elements = util::intersection(a, b);
         let mut node_replacements = Vec::new();
 
         for item in common_elements.clone() {
             match item {
                 util::AlignedItem::Node(a_node, b_node) => {
-                    let mut rng = rand::thread_rng();
-                    let (g, loc) = if rng.gen::<f32>() < 0.5 {
+                    let mut rng = thread_rng();
+                    let (g, loc, parent) = if rng.gen::<f32>() < 0.5 {
                         node_replacements.push(NodeReplacement {
                             from: b_node.clone(),
                             to: a_node.clone(),
                         });
-                        (&a.graph, a_node)
+                        (&a.graph, a_node, Parent::A)
                     } else {
                         node_replacements.push(NodeReplacement {
                             from: a_node.clone(),
                             to: b_node.clone(),
                         });
-                        (&b.graph, b_node)
+                        (&b.graph, b_node, Parent::B)
                     };
 
                     output
                         .graph
                         .create_node_at(&loc, g.get_node(&loc).unwrap().value.clone());
+                    output.record(ProvenanceEvent::NodeInherited { at: loc, parent });
                 }
                 _ => {}
             }
@@ -89,19 +144,20 @@ impl Reproducer for Crossover {
         for item in common_elements {
             match item {
                 util::AlignedItem::Edge { data, _type } => {
-                    let mut rng = rand::thread_rng();
-
-                    let mut choose_graph = |a_conn: ConnectionInfo,
-                                            b_conn: ConnectionInfo|
-                     -> (ConnectionInfo, &NeuralGraph) {
-                        if rng.gen::<f32>() < 0.5 {
-                            (a_conn, &a.graph)
-                        } else {
-                            (b_conn, &b.graph)
-                        }
-                    };
+                    let mut rng = thread_rng();
+
+                    let mut choose_graph =
+                        |a_conn: ConnectionInfo,
+                         b_conn: ConnectionInfo|
+                         -> (ConnectionInfo, &NeuralGraph, Parent) {
+                            if rng.gen::<f32>() < 0.5 {
+                                (a_conn, &a.graph, Parent::A)
+                            } else {
+                                (b_conn, &b.graph, Parent::B)
+                            }
+                        };
 
-                    let (conn, g) = choose_graph(data.0, data.1);
+                    let (conn, g, parent) = choose_graph(data.0, data.1);
                     let (graph_specific_conn, replaced_conn) = {
                         let (conn, from, to) = match _type {
                             util::EdgeType::Incoming => {
@@ -129,6 +185,11 @@ impl Reproducer for Crossover {
                             .value
                             .clone(),
                     )?;
+                    output.record(ProvenanceEvent::EdgeInherited {
+                        from: replaced_conn.0,
+                        to: replaced_conn.1,
+                        parent,
+                    });
                 }
                 _ => {}
             }
@@ -139,6 +200,227 @@ impl Reproducer for Crossover {
     }
 }
 
+/// NEAT style crossover, the child has the structure of the fitter parent
+/// and each matching edge takes its value from either parent at random
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct NeatCrossover;
+
+impl Reproducer for NeatCrossover {
+    fn reproduce(
+        &self,
+        a: &Net,
+        b: &Net,
+        fitness: &ParentFitness,
+        output: &mut Net,
+    ) -> Result<(), ReproduceError> {
+        let (fitter, other) = fitness.order(a, b);
+        output.copy_structure(fitter);
+        output.record(ProvenanceEvent::StructureInherited {
+            parent: fitness.fitter(),
+        });
+
+        let other_parent = match fitness.fitter() {
+            Parent::A => Parent::B,
+            Parent::B => Parent::A,
+        };
+        let mut rng = thread_rng();
+        let mut events = Vec::new();
+        for_each_matching_edge(output, &other.graph, |from, to, edge, other_edge| {
+            if rng.gen::<f32>() < 0.5 {
+                *edge = *other_edge;
+                events.push(ProvenanceEvent::EdgeInherited {
+                    from,
+                    to,
+                    parent: other_parent,
+                });
+            }
+        });
+        events.into_iter().for_each(|e| output.record(e));
+        Ok(())
+    }
+}
+
+/// Child has the structure of the fitter parent, matching edges get the mean weight of both parents
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct AverageCrossover;
+
+impl Reproducer for AverageCrossover {
+    fn reproduce(
+        &self,
+        a: &Net,
+        b: &Net,
+        fitness: &ParentFitness,
+        output: &mut Net,
+    ) -> Result<(), ReproduceError> {
+        let (fitter, other) = fitness.order(a, b);
+        output.copy_structure(fitter);
+        output.record(ProvenanceEvent::StructureInherited {
+            parent: fitness.fitter(),
+        });
+
+        let mut events = Vec::new();
+        for_each_matching_edge(output, &other.graph, |from, to, edge, other_edge| {
+            edge.weight = (edge.weight + other_edge.weight) / 2.0;
+            events.push(ProvenanceEvent::EdgeAveraged { from, to });
+        });
+        events.into_iter().for_each(|e| output.record(e));
+        Ok(())
+    }
+}
+
+fn for_each_matching_edge(
+    net: &mut Net,
+    other: &NeuralGraph,
+    mut f: impl FnMut(GraphLocation, GraphLocation, &mut Edge, &Edge),
+) {
+    let mut matches = Vec::new();
+    for (layer_idx, layer) in net.graph.layers.iter().enumerate() {
+        for (node_idx, node) in layer.iter().enumerate() {
+            let from = GraphLocation::new(layer_idx as GraphSize, node_idx as GraphSize);
+            for c in node.connections.iter() {
+                if let Some(other_edge) = matching_edge(&net.graph, &from, &c.to, other) {
+                    matches.push((from, c.to, other_edge.value));
+                }
+            }
+        }
+    }
+
+    for (from, to, other_edge) in matches {
+        if let Some(edge) = net.graph.get_edge_mut(&from, &to) {
+            f(from, to, &mut edge.value, &other_edge);
+        }
+    }
+}
+
+/// Splits the hidden layers at `points` random cuts and alternates which parent
+/// each segment is taken from, edges are reconnected by gene id
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LayerCrossover {
+    pub points: usize,
+}
+
+impl Default for LayerCrossover {
+    fn default() -> Self {
+        LayerCrossover { points: 1 }
+    }
+}
+
+impl Reproducer for LayerCrossover {
+    fn reproduce(
+        &self,
+        a: &Net,
+        b: &Net,
+        _: &ParentFitness,
+        output: &mut Net,
+    ) -> Result<(), ReproduceError> {
+        let mut rng = thread_rng();
+        let hidden = |n: &Net| n.graph.layers.len().saturating_sub(2);
+        let num_hidden = hidden(a).max(hidden(b));
+
+        let mut cuts = (1..num_hidden).collect::<Vec<_>>();
+        cuts.shuffle(&mut rng);
+        cuts.truncate(self.points);
+        cuts.sort();
+
+        let start_with_a = rng.gen::<bool>();
+        // parent for every layer of the child, input and output layers included
+        let sources = (0..num_hidden + 2)
+            .map(|layer| {
+                if layer == 0 || layer == num_hidden + 1 {
+                    return a;
+                }
+
+                let segment = cuts.iter().filter(|c| **c < layer).count();
+                let (first, second) = if (segment % 2 == 0) == start_with_a {
+                    (a, b)
+                } else {
+                    (b, a)
+                };
+                if hidden(first) >= layer {
+                    first
+                } else {
+                    second
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let mut g = NeuralGraph::new();
+        for (layer, source) in sources.iter().enumerate() {
+            let source_layer = if layer == num_hidden + 1 {
+                source.output_layer as usize
+            } else {
+                layer
+            };
+            g.add_layer_to_end();
+            for node in source.graph.layers[source_layer].iter() {
+                g.add_node(layer as GraphSize, GraphNode::new(node.value.clone()))?;
+            }
+        }
+
+        // input layer edges come from the parent of the first hidden layer
+        let edge_sources = sources
+            .iter()
+            .enumerate()
+            .map(|(layer, source)| match layer {
+                0 => sources.get(1).copied().unwrap_or(a),
+                _ => *source,
+            })
+            .take(num_hidden + 1);
+        for (layer, source) in edge_sources.enumerate() {
+            for (node_idx, node) in source.graph.layers[layer].iter().enumerate() {
+                let from = GraphLocation::new(layer as GraphSize, node_idx as GraphSize);
+                for c in node.connections.iter() {
+                    let to = source
+                        .graph
+                        .get_node(&c.to)
+                        .and_then(gene_id)
+                        .and_then(|id| find_gene(&g, id, layer as GraphSize + 1));
+                    if let Some(to) = to {
+                        if g.get_edge(&from, &to).is_none() {
+                            g.add_edge(from, to, c.value)?;
+                        }
+                    }
+                }
+            }
+        }
+
+        output.graph = g;
+        output.input_layer = 0;
+        output.output_layer = num_hidden as GraphSize + 1;
+        for (layer, source) in sources.iter().enumerate().take(num_hidden + 1).skip(1) {
+            output.record(ProvenanceEvent::LayerInherited {
+                layer,
+                parent: if std::ptr::eq(*source, a) {
+                    Parent::A
+                } else {
+                    Parent::B
+                },
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Asexual reproduction, the child is an exact copy of the fitter parent
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct CloneParent;
+
+impl Reproducer for CloneParent {
+    fn reproduce(
+        &self,
+        a: &Net,
+        b: &Net,
+        fitness: &ParentFitness,
+        output: &mut Net,
+    ) -> Result<(), ReproduceError> {
+        output.copy_structure(fitness.order(a, b).0);
+        output.record(ProvenanceEvent::StructureInherited {
+            parent: fitness.fitter(),
+        });
+        Ok(())
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct DefaultIterator(usize);
 
@@ -165,9 +447,15 @@ mod test {
     use crate::{
         activations::Sigmoid,
         nn::{
-            reproduce::{Crossover, Reproducer},
+            mutate::{AddNeuron, FixedCount},
+            next_innovation,
+            reproduce::{
+                AverageCrossover, CloneParent, Crossover, DefaultIterator, LayerCrossover,
+                NeatCrossover, ParentFitness, Reproducer,
+            },
             BasicNeuron, Edge, GraphLocation, GraphNode, Net, Node,
         },
+        NeuronInfo,
     };
 
     use crate::nn::test_requirements::*;
@@ -206,6 +494,7 @@ mod test {
             graph: graph_a,
             input_layer: 0,
             output_layer: 2,
+            ..Default::default()
         };
 
         let mut graph_b = create_graph(&input_nodes, &output_nodes);
@@ -228,10 +517,11 @@ mod test {
             graph: graph_b,
             input_layer: 0,
             output_layer: 2,
+            ..Default::default()
         };
 
         let mut output = Net::from_preserving_basic(&a).expect("Could not create neural net from A");
-        Crossover::default().reproduce(&a, &b, &mut output).expect("Could not crossover");
+        Crossover.reproduce(&a, &b, &ParentFitness::default(), &mut output).expect("Could not crossover");
 
         let mut compose_output = create_graph(&input_nodes, &output_nodes);
 
@@ -255,4 +545,215 @@ mod test {
 
         assert_eq!(bincode::serialize(&compose_output).unwrap(), bincode::serialize(&output.graph).unwrap());
     }
+
+    fn edge(weight: f32) -> Edge {
+        Edge {
+            weight,
+            enabled: true,
+            ..Default::default()
+        }
+    }
+
+    /// Two parents sharing the edge 0,0 -> 1,0 with differing weights,
+    /// A also has a hidden neuron while B has an extra direct edge
+    #[rustfmt::skip]
+    fn parents() -> (Net, Net) {
+        let input_nodes = [
+            Node::Input(Box::new(BlankInput::new(0.0, 0))),
+            Node::Input(Box::new(BlankInput::new(0.0, 1))),
+        ];
+        let output_nodes = [Node::Output(Sigmoid::new(0.0, 2, "a".to_owned()))];
+
+        let mut graph_a = create_graph(&input_nodes, &output_nodes);
+        graph_a.add_layer(1);
+        graph_a.add_node(1, GraphNode::new(Node::Neuron(Box::new(BasicNeuron { bias: 0.0, id: 5 })))).unwrap();
+        graph_a.add_edge(GraphLocation::new(0, 0), GraphLocation::new(2, 0), edge(1.0)).unwrap();
+        graph_a.add_edge(GraphLocation::new(0, 1), GraphLocation::new(1, 0), edge(0.5)).unwrap();
+        graph_a.add_edge(GraphLocation::new(1, 0), GraphLocation::new(2, 0), edge(0.5)).unwrap();
+
+        let mut graph_b = create_graph(&input_nodes, &output_nodes);
+        graph_b.add_edge(GraphLocation::new(0, 0), GraphLocation::new(1, 0), edge(3.0)).unwrap();
+        graph_b.add_edge(GraphLocation::new(0, 1), GraphLocation::new(1, 0), edge(3.0)).unwrap();
+
+        (
+            Net { graph: graph_a, input_layer: 0, output_layer: 2, ..Default::default() },
+            Net { graph: graph_b, input_layer: 0, output_layer: 1, ..Default::default() },
+        )
+    }
+
+    #[test]
+    fn neat_crossover() {
+        let (a, b) = parents();
+        let mut output = Net::from_preserving_basic(&a).unwrap();
+        NeatCrossover
+            .reproduce(&a, &b, &ParentFitness::new(2.0, 1.0), &mut output)
+            .unwrap();
+
+        // structure, including the disjoint hidden neuron, comes from the fitter parent
+        assert_eq!(output.graph.layers.len(), 3);
+        assert_eq!(output.graph.num_edges(), 3);
+        let shared = output
+            .graph
+            .get_edge(&GraphLocation::new(0, 0), &GraphLocation::new(2, 0))
+            .unwrap();
+        assert!(shared.value.weight == 1.0 || shared.value.weight == 3.0);
+
+        NeatCrossover
+            .reproduce(&a, &b, &ParentFitness::new(1.0, 2.0), &mut output)
+            .unwrap();
+        assert_eq!(output.graph.layers.len(), 2);
+        assert_eq!(output.graph.num_edges(), 2);
+    }
+
+    #[test]
+    fn average_crossover() {
+        let (a, b) = parents();
+        let mut output = Net::default();
+        AverageCrossover
+            .reproduce(&a, &b, &ParentFitness::default(), &mut output)
+            .unwrap();
+
+        let shared = output
+            .graph
+            .get_edge(&GraphLocation::new(0, 0), &GraphLocation::new(2, 0))
+            .unwrap();
+        assert_eq!(shared.value.weight, 2.0);
+        let unmatched = output
+            .graph
+            .get_edge(&GraphLocation::new(0, 1), &GraphLocation::new(1, 0))
+            .unwrap();
+        assert_eq!(unmatched.value.weight, 0.5);
+    }
+
+    #[test]
+    fn layer_crossover() {
+        let (a, b) = parents();
+        let mut output = Net::default();
+        for _ in 0..10 {
+            LayerCrossover { points: 2 }
+                .reproduce(&a, &b, &ParentFitness::default(), &mut output)
+                .unwrap();
+
+            // B has no hidden layers, so the only hidden layer always comes from A
+            assert_eq!(output.graph.layers.len(), 3);
+            assert_eq!(output.output_layer, 2);
+            for layer in output.graph.layers.iter() {
+                for node in layer.iter() {
+                    for c in node.connections.iter() {
+                        assert!(output.graph.get_node(&c.to).is_some());
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn clone_parent() {
+        let (a, b) = parents();
+        let mut output = Net::default();
+        CloneParent
+            .reproduce(&a, &b, &ParentFitness::new(0.0, 1.0), &mut output)
+            .unwrap();
+        assert_eq!(
+            bincode::serialize(&output).unwrap(),
+            bincode::serialize(&b).unwrap()
+        );
+    }
+
+    #[test]
+    fn mixed_reproducers() {
+        let (a, b) = parents();
+        let types: Vec<Box<dyn Reproducer>> =
+            vec![Box::new(CloneParent), Box::new(AverageCrossover)];
+        let child = Net::reproduce(
+            &a,
+            &b,
+            ParentFitness::new(1.0, 0.0),
+            &types,
+            DefaultIterator::new(),
+        )
+        .unwrap();
+        assert_eq!(child.graph.num_edges(), 3);
+    }
+
+    /// Sets every edge weight in `net`
+    fn with_weights(mut net: Net, weight: f32) -> Net {
+        net.graph
+            .layers
+            .iter_mut()
+            .flatten()
+            .flat_map(|node| node.connections.iter_mut())
+            .for_each(|c| c.value.weight = weight);
+        net
+    }
+
+    #[test]
+    fn separately_added_neurons_do_not_match() {
+        let input_nodes = [Node::Input(Box::new(BlankInput::new(0.0, 0)))];
+        let output_nodes = [Node::Output(Sigmoid::new(0.0, 1, "a".to_owned()))];
+        let mut graph = create_graph(&input_nodes, &output_nodes);
+        let split = Edge {
+            innovation: next_innovation(),
+            ..edge(1.0)
+        };
+        graph
+            .add_edge(GraphLocation::new(0, 0), GraphLocation::new(1, 0), split)
+            .unwrap();
+        let base = Net {
+            graph,
+            input_layer: 0,
+            output_layer: 1,
+            ..Default::default()
+        };
+
+        // each parent splits the same edge with its own hidden neuron
+        let add_neuron = |mut net: Net| {
+            net.mutate(
+                &[],
+                &[Box::new(AddNeuron)],
+                &[Box::new(BasicNeuron::default())],
+                &|_| 0,
+                FixedCount::new(1).unwrap(),
+            )
+            .unwrap();
+            net
+        };
+        let a = add_neuron(base.clone());
+        let b = add_neuron(base);
+        let hidden = GraphLocation::new(1, 0);
+        assert_ne!(
+            a.graph.get_node(&hidden).unwrap().value.id(),
+            b.graph.get_node(&hidden).unwrap().value.id()
+        );
+
+        let a = with_weights(a, 0.0);
+        let mut output = Net::default();
+        AverageCrossover
+            .reproduce(
+                &a,
+                &with_weights(b, 1.0),
+                &ParentFitness::new(1.0, 0.0),
+                &mut output,
+            )
+            .unwrap();
+        for (from, to) in [((0, 0), (1, 0)), ((1, 0), (2, 0))] {
+            let from = GraphLocation::new(from.0, from.1);
+            let to = GraphLocation::new(to.0, to.1);
+            assert_eq!(output.graph.get_edge(&from, &to).unwrap().value.weight, 0.0);
+        }
+
+        // the same neuron in both parents still matches
+        let c = with_weights(a.clone(), 1.0);
+        AverageCrossover
+            .reproduce(&a, &c, &ParentFitness::new(1.0, 0.0), &mut output)
+            .unwrap();
+        assert_eq!(output.graph.num_edges(), 2);
+        output
+            .graph
+            .layers
+            .iter()
+            .flatten()
+            .flat_map(|node| node.connections.iter())
+            .for_each(|c| assert_eq!(c.value.weight, 0.5));
+    }
 }
@@ -5,7 +5,7 @@ use crate::{
     NeuronInfo,
 };
 
-use super::{GraphNode, GraphSize};
+use super::{GraphEdge, GraphNode, GraphSize, NeuralGraph, Node};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(bound(deserialize = "'de: 'static"))]
@@ -196,3 +196,52 @@ pub fn connection_pair_exists(
         .position(|x| (x.0 == *from && x.1 == *to) || (x.0 == *to && x.1 == *from))
         .map_or_else(|| false, |_| true)
 }
+
+/// Identifies the same gene across nets as (type, id)
+pub type GeneId = (&'static str, usize);
+
+pub fn gene_id(node: &GraphNode) -> Option<GeneId> {
+    match node.value {
+        Node::None => None,
+        _ => Some((node.value._type(), node.value.id())),
+    }
+}
+
+/// First node with the given gene id, only searching layers at or after `from_layer`
+pub fn find_gene(g: &NeuralGraph, id: GeneId, from_layer: GraphSize) -> Option<GraphLocation> {
+    g.layers
+        .iter()
+        .enumerate()
+        .skip(from_layer as usize)
+        .find_map(|(layer_idx, layer)| {
+            layer
+                .iter()
+                .position(|node| gene_id(node) == Some(id))
+                .map(|node_idx| GraphLocation::new(layer_idx as GraphSize, node_idx as GraphSize))
+        })
+}
+
+/// The edge in `other` with the innovation of `from` -> `to` in `g`,
+/// edges without one match the edge connecting the same genes
+pub fn matching_edge<'a>(
+    g: &NeuralGraph,
+    from: &GraphLocation,
+    to: &GraphLocation,
+    other: &'a NeuralGraph,
+) -> Option<&'a GraphEdge> {
+    let innovation = g.get_edge(from, to)?.value.innovation;
+    if innovation != 0 {
+        return other
+            .layers
+            .iter()
+            .flatten()
+            .flat_map(|node| node.connections.iter())
+            .find(|c| c.value.innovation == innovation);
+    }
+
+    let from_id = gene_id(g.get_node(from)?)?;
+    let to_id = gene_id(g.get_node(to)?)?;
+    let other_from = find_gene(other, from_id, 0)?;
+    let other_to = find_gene(other, to_id, other_from.layer + 1)?;
+    other.get_edge(&other_from, &other_to)
+}
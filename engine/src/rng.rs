@@ -0,0 +1,70 @@
+use std::cell::RefCell;
+
+use rand::{
+    distributions::{Distribution, Standard},
+    rngs::StdRng,
+    Rng, RngCore, SeedableRng,
+};
+
+thread_local! {
+    static RNG: RefCell<StdRng> = RefCell::new(StdRng::from_entropy());
+}
+
+/// Reseeds the calling thread's generator, everything drawn from it afterwards repeats
+/// for the same seed
+pub fn seed(seed: u64) {
+    RNG.with(|rng| *rng.borrow_mut() = StdRng::seed_from_u64(seed));
+}
+
+/// Handle to the calling thread's generator, the engine draws all its randomness from it
+/// so generating, mutating and reproducing nets can be replayed with [`seed`]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ThreadRng;
+
+pub fn thread_rng() -> ThreadRng {
+    ThreadRng
+}
+
+/// Like `rand::random`, from the calling thread's generator
+pub fn random<T>() -> T
+where
+    Standard: Distribution<T>,
+{
+    thread_rng().gen()
+}
+
+impl RngCore for ThreadRng {
+    fn next_u32(&mut self) -> u32 {
+        RNG.with(|rng| rng.borrow_mut().next_u32())
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        RNG.with(|rng| rng.borrow_mut().next_u64())
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        RNG.with(|rng| rng.borrow_mut().fill_bytes(dest))
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        RNG.with(|rng| rng.borrow_mut().try_fill_bytes(dest))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand::Rng;
+
+    use super::{seed, thread_rng};
+
+    #[test]
+    fn same_seed_same_numbers() {
+        let draw = || (0..8).map(|_| thread_rng().gen()).collect::<Vec<u64>>();
+        seed(3);
+        let a = draw();
+        seed(3);
+        assert_eq!(a, draw());
+        seed(4);
+        assert_ne!(a, draw());
+    }
+}
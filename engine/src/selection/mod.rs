@@ -0,0 +1,62 @@
+use rand::Rng;
+
+use crate::rng::thread_rng;
+
+pub mod novelty;
+pub mod nsga;
+
+/// Index of the highest scoring of `size` randomly drawn individuals
+pub fn tournament(scores: &[f32], size: usize) -> Option<usize> {
+    if scores.is_empty() {
+        return None;
+    }
+
+    let mut rng = thread_rng();
+    (0..size.max(1))
+        .map(|_| rng.gen_range(0..scores.len()))
+        .reduce(|best, idx| {
+            if scores[idx] > scores[best] {
+                idx
+            } else {
+                best
+            }
+        })
+}
+
+/// Pairs as (A, B), each parent picked by its own tournament
+pub fn select_pairs(scores: &[f32], count: usize, size: usize) -> Vec<(usize, usize)> {
+    (0..count)
+        .filter_map(|_| Some((tournament(scores, size)?, tournament(scores, size)?)))
+        .collect()
+}
+
+/// Rescales scores into `[0, 1]`, all equal scores map to 0
+pub fn normalize(scores: &[f32]) -> Vec<f32> {
+    let min = scores.iter().cloned().fold(f32::INFINITY, f32::min);
+    let max = scores.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
+    let range = max - min;
+    scores
+        .iter()
+        .map(|x| if range > 0.0 { (x - min) / range } else { 0.0 })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{normalize, select_pairs, tournament};
+
+    #[test]
+    fn tournament_picks_best() {
+        let scores = [0.1, 5.0, 0.3];
+        // drawing many times makes it all but certain the best is in the tournament
+        assert_eq!(tournament(&scores, 64), Some(1));
+        assert_eq!(tournament(&[], 3), None);
+        assert_eq!(select_pairs(&scores, 4, 64), vec![(1, 1); 4]);
+    }
+
+    #[test]
+    fn normalize_range() {
+        assert_eq!(normalize(&[2.0, 4.0, 3.0]), vec![0.0, 1.0, 0.5]);
+        assert_eq!(normalize(&[1.0, 1.0]), vec![0.0, 0.0]);
+    }
+}
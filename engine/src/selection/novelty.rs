@@ -0,0 +1,209 @@
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::normalize;
+
+/// Behaviour characterisation of a single individual, e.g. final position or food eaten
+pub type Descriptor = Vec<f32>;
+
+#[derive(Debug, Error)]
+pub enum NoveltyError {
+    #[error("Descriptor has {found} dimensions, expected {expected}")]
+    DimensionMismatch { expected: usize, found: usize },
+    #[error("Got {found} scores for {expected} individuals")]
+    LengthMismatch { expected: usize, found: usize },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NoveltyArchive {
+    /// Number of nearest neighbours averaged into the novelty score
+    pub k: usize,
+    /// Individuals scoring above this are added to the archive
+    pub threshold: f32,
+    /// Oldest entries are evicted once the archive grows past this
+    pub max_size: Option<usize>,
+    archive: VecDeque<Descriptor>,
+}
+
+impl Default for NoveltyArchive {
+    fn default() -> Self {
+        NoveltyArchive::new(15, 1.0, None)
+    }
+}
+
+impl NoveltyArchive {
+    pub fn new(k: usize, threshold: f32, max_size: Option<usize>) -> NoveltyArchive {
+        NoveltyArchive {
+            k,
+            threshold,
+            max_size,
+            archive: VecDeque::new(),
+        }
+    }
+
+    pub fn archive(&self) -> &VecDeque<Descriptor> {
+        &self.archive
+    }
+
+    pub fn clear(&mut self) {
+        self.archive.clear();
+    }
+
+    /// Mean distance of every individual to its k nearest neighbours among the population and the archive
+    pub fn score(&self, population: &[Descriptor]) -> Result<Vec<f32>, NoveltyError> {
+        let dims = match population.first().or(self.archive.front()) {
+            Some(d) => d.len(),
+            None => return Ok(Vec::new()),
+        };
+        if let Some(d) = population
+            .iter()
+            .chain(self.archive.iter())
+            .find(|d| d.len() != dims)
+        {
+            return Err(NoveltyError::DimensionMismatch {
+                expected: dims,
+                found: d.len(),
+            });
+        }
+
+        Ok(population
+            .iter()
+            .enumerate()
+            .map(|(idx, item)| {
+                let mut distances = population
+                    .iter()
+                    .enumerate()
+                    .filter(|(other_idx, _)| *other_idx != idx)
+                    .map(|(_, other)| distance(item, other))
+                    .chain(self.archive.iter().map(|other| distance(item, other)))
+                    .collect::<Vec<_>>();
+                if distances.is_empty() {
+                    return 0.0;
+                }
+
+                let k = self.k.clamp(1, distances.len());
+                distances.select_nth_unstable_by(k - 1, |a, b| a.total_cmp(b));
+                distances[..k].iter().sum::<f32>() / k as f32
+            })
+            .collect())
+    }
+
+    /// Archives every individual whose score passed the threshold, returns how many were added
+    pub fn update(
+        &mut self,
+        population: &[Descriptor],
+        scores: &[f32],
+    ) -> Result<usize, NoveltyError> {
+        if population.len() != scores.len() {
+            return Err(NoveltyError::LengthMismatch {
+                expected: population.len(),
+                found: scores.len(),
+            });
+        }
+
+        let mut added = 0;
+        for (d, _) in population
+            .iter()
+            .zip(scores)
+            .filter(|(_, s)| **s > self.threshold)
+        {
+            self.archive.push_back(d.clone());
+            added += 1;
+        }
+
+        if let Some(max_size) = self.max_size {
+            while self.archive.len() > max_size {
+                self.archive.pop_front();
+            }
+        }
+        Ok(added)
+    }
+
+    /// Scores the population and then archives the novel individuals
+    pub fn evaluate(&mut self, population: &[Descriptor]) -> Result<Vec<f32>, NoveltyError> {
+        let scores = self.score(population)?;
+        self.update(population, &scores)?;
+        Ok(scores)
+    }
+}
+
+fn distance(a: &[f32], b: &[f32]) -> f32 {
+    a.iter()
+        .zip(b)
+        .map(|(a, b)| (a - b).powi(2))
+        .sum::<f32>()
+        .sqrt()
+}
+
+/// Weighted sum of normalized fitness and novelty, a weight of 1.0 is pure novelty search
+pub fn blend(fitness: &[f32], novelty: &[f32], weight: f32) -> Result<Vec<f32>, NoveltyError> {
+    if fitness.len() != novelty.len() {
+        return Err(NoveltyError::LengthMismatch {
+            expected: fitness.len(),
+            found: novelty.len(),
+        });
+    }
+
+    let weight = weight.clamp(0.0, 1.0);
+    Ok(normalize(fitness)
+        .into_iter()
+        .zip(normalize(novelty))
+        .map(|(f, n)| (1.0 - weight) * f + weight * n)
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{blend, NoveltyArchive, NoveltyError};
+
+    #[test]
+    fn knn_score() {
+        let archive = NoveltyArchive::new(1, 1.0, None);
+        let population = vec![vec![0.0, 0.0], vec![1.0, 0.0], vec![10.0, 0.0]];
+        let scores = archive.score(&population).unwrap();
+        assert_eq!(scores, vec![1.0, 1.0, 9.0]);
+
+        let archive = NoveltyArchive::new(2, 1.0, None);
+        let scores = archive.score(&population).unwrap();
+        assert_eq!(scores, vec![5.5, 5.0, 9.5]);
+    }
+
+    #[test]
+    fn archive_growth() {
+        let mut archive = NoveltyArchive::new(1, 2.0, Some(1));
+        let population = vec![vec![0.0], vec![1.0], vec![10.0]];
+        assert_eq!(archive.evaluate(&population).unwrap(), vec![1.0, 1.0, 9.0]);
+        assert_eq!(archive.archive().len(), 1);
+        assert_eq!(archive.archive()[0], vec![10.0]);
+
+        // the archived individual now counts as a neighbour
+        assert_eq!(archive.score(&[vec![9.0]]).unwrap(), vec![1.0]);
+
+        archive.update(&[vec![20.0]], &[3.0]).unwrap();
+        assert_eq!(archive.archive()[0], vec![20.0]);
+    }
+
+    #[test]
+    fn mismatched_input() {
+        let archive = NoveltyArchive::default();
+        assert!(matches!(
+            archive.score(&[vec![0.0], vec![0.0, 1.0]]),
+            Err(NoveltyError::DimensionMismatch {
+                expected: 1,
+                found: 2
+            })
+        ));
+        assert!(blend(&[1.0], &[], 0.5).is_err());
+    }
+
+    #[test]
+    fn blend_scores() {
+        let fitness = [0.0, 10.0];
+        let novelty = [4.0, 2.0];
+        assert_eq!(blend(&fitness, &novelty, 0.0).unwrap(), vec![0.0, 1.0]);
+        assert_eq!(blend(&fitness, &novelty, 1.0).unwrap(), vec![1.0, 0.0]);
+        assert_eq!(blend(&fitness, &novelty, 0.5).unwrap(), vec![0.5, 0.5]);
+    }
+}
@@ -0,0 +1,253 @@
+use std::cmp::Ordering;
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{nn::Net, rng::thread_rng};
+
+/// Objective values of a single individual, every objective is maximised
+pub type Objectives = Vec<f32>;
+
+#[derive(Debug, Error)]
+pub enum NsgaError {
+    #[error("Objective vector has {found} values, expected {expected}")]
+    DimensionMismatch { expected: usize, found: usize },
+    #[error("Got {found} nets for {expected} objective vectors")]
+    LengthMismatch { expected: usize, found: usize },
+}
+
+/// True if `a` is no worse than `b` in every objective and better in at least one
+pub fn dominates(a: &[f32], b: &[f32]) -> bool {
+    let mut better = false;
+    for (a, b) in a.iter().zip(b) {
+        if a < b {
+            return false;
+        }
+        better |= a > b;
+    }
+    better
+}
+
+/// Fronts in order of rank, the first front is the pareto front
+pub fn non_dominated_fronts(objectives: &[Objectives]) -> Vec<Vec<usize>> {
+    let mut dominated_by = vec![0usize; objectives.len()];
+    let mut dominating = vec![Vec::new(); objectives.len()];
+    for i in 0..objectives.len() {
+        for j in (i + 1)..objectives.len() {
+            if dominates(&objectives[i], &objectives[j]) {
+                dominating[i].push(j);
+                dominated_by[j] += 1;
+            } else if dominates(&objectives[j], &objectives[i]) {
+                dominating[j].push(i);
+                dominated_by[i] += 1;
+            }
+        }
+    }
+
+    let mut fronts = Vec::new();
+    let mut current = (0..objectives.len())
+        .filter(|i| dominated_by[*i] == 0)
+        .collect::<Vec<_>>();
+    while !current.is_empty() {
+        let mut next = Vec::new();
+        for i in current.iter() {
+            for j in dominating[*i].iter() {
+                dominated_by[*j] -= 1;
+                if dominated_by[*j] == 0 {
+                    next.push(*j);
+                }
+            }
+        }
+        fronts.push(current);
+        current = next;
+    }
+    fronts
+}
+
+/// Crowding distance of every member of `front`, boundary individuals are infinitely far
+pub fn crowding_distance(objectives: &[Objectives], front: &[usize]) -> Vec<f32> {
+    let mut distance = vec![0.0; front.len()];
+    if front.len() <= 2 {
+        return vec![f32::INFINITY; front.len()];
+    }
+
+    let num_objectives = objectives[front[0]].len();
+    let mut order = (0..front.len()).collect::<Vec<_>>();
+    for column in (0..num_objectives).map(|m| {
+        front
+            .iter()
+            .map(|idx| objectives[*idx][m])
+            .collect::<Vec<_>>()
+    }) {
+        order.sort_by(|a, b| column[*a].total_cmp(&column[*b]));
+        let first = order[0];
+        let last = order[order.len() - 1];
+        distance[first] = f32::INFINITY;
+        distance[last] = f32::INFINITY;
+
+        let range = column[last] - column[first];
+        if range <= 0.0 {
+            continue;
+        }
+        for w in order.windows(3) {
+            distance[w[1]] += (column[w[2]] - column[w[0]]) / range;
+        }
+    }
+    distance
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Ranking {
+    /// Front index of every individual, lower is better
+    pub rank: Vec<usize>,
+    pub crowding: Vec<f32>,
+}
+
+impl Ranking {
+    pub fn new(objectives: &[Objectives]) -> Result<Ranking, NsgaError> {
+        if let Some(first) = objectives.first() {
+            if let Some(o) = objectives.iter().find(|o| o.len() != first.len()) {
+                return Err(NsgaError::DimensionMismatch {
+                    expected: first.len(),
+                    found: o.len(),
+                });
+            }
+        }
+
+        let mut rank = vec![0; objectives.len()];
+        let mut crowding = vec![0.0; objectives.len()];
+        for (front_idx, front) in non_dominated_fronts(objectives).into_iter().enumerate() {
+            for (idx, distance) in front.iter().zip(crowding_distance(objectives, &front)) {
+                rank[*idx] = front_idx;
+                crowding[*idx] = distance;
+            }
+        }
+        Ok(Ranking { rank, crowding })
+    }
+
+    /// Crowded comparison, lower rank wins and ties go to the less crowded individual
+    pub fn compare(&self, a: usize, b: usize) -> Ordering {
+        self.rank[b]
+            .cmp(&self.rank[a])
+            .then(self.crowding[a].total_cmp(&self.crowding[b]))
+    }
+
+    /// Binary tournament using the crowded comparison
+    pub fn tournament(&self) -> Option<usize> {
+        if self.rank.is_empty() {
+            return None;
+        }
+
+        let mut rng = thread_rng();
+        let a = rng.gen_range(0..self.rank.len());
+        let b = rng.gen_range(0..self.rank.len());
+        Some(match self.compare(a, b) {
+            Ordering::Less => b,
+            _ => a,
+        })
+    }
+
+    /// Pairs as (A, B), ready to be passed to `Net::reproduce`
+    pub fn select_pairs(&self, count: usize) -> Vec<(usize, usize)> {
+        (0..count)
+            .filter_map(|_| Some((self.tournament()?, self.tournament()?)))
+            .collect()
+    }
+
+    /// The best `count` individuals, best first
+    pub fn best(&self, count: usize) -> Vec<usize> {
+        let mut order = (0..self.rank.len()).collect::<Vec<_>>();
+        order.sort_by(|a, b| self.compare(*b, *a));
+        order.truncate(count);
+        order
+    }
+}
+
+/// Number of nodes plus number of edges in the net
+pub fn complexity(net: &Net) -> usize {
+    net.graph.num_nodes() + net.graph.num_edges()
+}
+
+/// Appends negated complexity as an objective, putting pressure on smaller brains
+pub fn with_parsimony(
+    objectives: &[Objectives],
+    nets: &[&Net],
+) -> Result<Vec<Objectives>, NsgaError> {
+    if objectives.len() != nets.len() {
+        return Err(NsgaError::LengthMismatch {
+            expected: objectives.len(),
+            found: nets.len(),
+        });
+    }
+
+    Ok(objectives
+        .iter()
+        .zip(nets)
+        .map(|(o, net)| {
+            let mut o = o.clone();
+            o.push(-(complexity(net) as f32));
+            o
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        activations::Sigmoid,
+        nn::{test_requirements::*, Edge, GraphLocation, Net, Node},
+    };
+
+    use super::{
+        complexity, crowding_distance, dominates, non_dominated_fronts, with_parsimony, Ranking,
+    };
+
+    #[test]
+    fn fronts() {
+        let objectives = vec![
+            vec![1.0, 5.0],
+            vec![2.0, 4.0],
+            vec![1.0, 1.0],
+            vec![3.0, 3.0],
+            vec![0.5, 0.5],
+        ];
+        assert!(dominates(&objectives[3], &objectives[2]));
+        assert!(!dominates(&objectives[0], &objectives[1]));
+
+        let fronts = non_dominated_fronts(&objectives);
+        assert_eq!(fronts, vec![vec![0, 1, 3], vec![2], vec![4]]);
+
+        let distance = crowding_distance(&objectives, &fronts[0]);
+        assert_eq!(distance[0], f32::INFINITY);
+        assert_eq!(distance[2], f32::INFINITY);
+        assert_eq!(distance[1], 2.0);
+
+        let ranking = Ranking::new(&objectives).unwrap();
+        assert_eq!(ranking.rank, vec![0, 0, 1, 0, 2]);
+        assert_eq!(ranking.best(3), vec![0, 3, 1]);
+        assert!(Ranking::new(&[vec![1.0], vec![1.0, 2.0]]).is_err());
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn parsimony() {
+        let input_nodes = [Node::Input(Box::new(BlankInput::new(0.0, 0)))];
+        let output_nodes = [Node::Output(Sigmoid::new(0.0, 1, "a".to_owned()))];
+
+        let small = Net {
+            graph: create_graph(&input_nodes, &output_nodes),
+            input_layer: 0,
+            output_layer: 1,
+            ..Default::default()
+        };
+        let mut big = small.clone();
+        big.graph.add_edge(GraphLocation::new(0, 0), GraphLocation::new(1, 0), Edge::default()).unwrap();
+        assert_eq!(complexity(&small), 2);
+        assert_eq!(complexity(&big), 3);
+
+        // equal fitness, so the smaller net dominates
+        let objectives = with_parsimony(&[vec![1.0], vec![1.0]], &[&small, &big]).unwrap();
+        assert_eq!(non_dominated_fronts(&objectives), vec![vec![0], vec![1]]);
+    }
+}
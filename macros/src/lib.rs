@@ -31,6 +31,10 @@ pub fn derive_neuron_info(input: TokenStream) -> TokenStream {
             fn id(&self) -> usize {
                 self.id
             }
+
+            fn set_id(&mut self, id: usize) {
+                self.id = id;
+            }
         }
     }
     .into()
@@ -97,6 +97,78 @@ impl Speed {
     }
 }
 
+/// Distance along a vision ray to the nearest hit, 0 is touching and 1 is nothing in range
+#[derive(Debug, Serialize, Deserialize, Clone, DNeuronInfo, SubTraits)]
+pub struct RayDistance {
+    value: f32,
+    id: usize,
+}
+
+#[typetag::serde]
+impl InputNeuron for RayDistance {
+    fn as_standard(&self) -> f32 {
+        self.value
+    }
+
+    fn set_value(&mut self, value: f32) {
+        self.value = value;
+    }
+}
+
+impl RayDistance {
+    pub fn new(value: f32, id: usize) -> Box<dyn InputNeuron> {
+        Box::new(Self { value, id })
+    }
+}
+
+/// What a vision ray hit, encoded by `HitKind::as_input`
+#[derive(Debug, Serialize, Deserialize, Clone, DNeuronInfo, SubTraits)]
+pub struct RayHit {
+    value: f32,
+    id: usize,
+}
+
+#[typetag::serde]
+impl InputNeuron for RayHit {
+    fn as_standard(&self) -> f32 {
+        self.value
+    }
+
+    fn set_value(&mut self, value: f32) {
+        self.value = value;
+    }
+}
+
+impl RayHit {
+    pub fn new(value: f32, id: usize) -> Box<dyn InputNeuron> {
+        Box::new(Self { value, id })
+    }
+}
+
+/// Resource field level under the creature, scaled to 0..1
+#[derive(Debug, Serialize, Deserialize, Clone, DNeuronInfo, SubTraits)]
+pub struct ResourceLevel {
+    value: f32,
+    id: usize,
+}
+
+#[typetag::serde]
+impl InputNeuron for ResourceLevel {
+    fn as_standard(&self) -> f32 {
+        self.value
+    }
+
+    fn set_value(&mut self, value: f32) {
+        self.value = value;
+    }
+}
+
+impl ResourceLevel {
+    pub fn new(value: f32, id: usize) -> Box<dyn InputNeuron> {
+        Box::new(Self { value, id })
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, DNeuronInfo, SubTraits)]
 pub struct BlankInput {
     value: f32,
@@ -129,6 +201,20 @@ impl engine::nn::Neuron for TestNeuronA {
     fn finish(&self, partial: f32) -> f32 {
         partial + self.value
     }
+
+    fn bias(&self) -> Option<f32> {
+        Some(self.value)
+    }
+
+    fn set_bias(&mut self, bias: f32) {
+        self.value = bias;
+    }
+}
+
+impl TestNeuronA {
+    pub fn new(value: f32, id: usize) -> Box<dyn engine::nn::Neuron> {
+        Box::new(Self { value, id })
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, DNeuronInfo, SubTraits)]
@@ -146,4 +232,18 @@ impl engine::nn::Neuron for TestNeuronB {
     fn finish(&self, partial: f32) -> f32 {
         partial + self.value
     }
+
+    fn bias(&self) -> Option<f32> {
+        Some(self.value)
+    }
+
+    fn set_bias(&mut self, bias: f32) {
+        self.value = bias;
+    }
+}
+
+impl TestNeuronB {
+    pub fn new(value: f32, id: usize) -> Box<dyn engine::nn::Neuron> {
+        Box::new(Self { value, id })
+    }
 }
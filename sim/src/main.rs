@@ -4,8 +4,6 @@ use bevy::{
     window::{PresentMode, WindowMode, WindowRef},
 };
 use bevy_egui::EguiPlugin;
-use engine::{activations::Sigmoid, nn::Node};
-use inputs::*;
 use main_menu::MainMenuPlugin;
 use net::{resources::InspectNet, NeuralNetPlugin};
 use sim::SimulationPlugin;
@@ -30,13 +28,17 @@ pub enum InspectWindowState {
     Display,
 }
 
-#[derive(Resource, Debug)]
-pub struct BaseNodes {
-    pub input_nodes: Vec<Node>,
-    pub output_nodes: Vec<Node>,
-}
+/// The primary window's camera, moved around by the simulation's view controls
+#[derive(Component)]
+pub struct WorldCamera;
 
 fn main() {
+    let args = std::env::args().collect::<Vec<_>>();
+    if args.get(1).is_some_and(|a| a == "--headless") {
+        sim::run_headless(&args[2..]);
+        return;
+    }
+
     let mut window_plugin = WindowPlugin {
         primary_window: Some(Window {
             title: "Visualize NN".into(),
@@ -52,31 +54,10 @@ fn main() {
         .unwrap()
         .set_maximized(true);
 
-    let input_nodes = vec![
-        Node::Input(Hunger::new(0.0, 0)),
-        Node::Input(Age::new(0, 1)),
-        Node::Input(Health::new(100.0, 2)),
-        Node::Input(Speed::new(0.0, 3)),
-    ];
-
-    let output_nodes = vec![
-        Node::Output(Sigmoid::new(0.0, 4, "forward".to_string())), // ::<Forward>
-        Node::Output(Sigmoid::new(0.0, 5, "backward".to_string())), // ::<Backward>
-        Node::Output(Sigmoid::new(0.0, 6, "left".to_string())),    // ::<Left>
-        Node::Output(Sigmoid::new(0.0, 7, "right".to_string())),   // ::<Right>
-        Node::Output(Sigmoid::new(0.0, 8, "output_speed".to_string())), // ::<OutputSpeed>
-        Node::Output(Sigmoid::new(0.0, 9, "mate".to_string())),    // ::<Mate>
-        Node::Output(Sigmoid::new(0.0, 10, "eat".to_string())),    // ::<Eat>
-    ];
-
     App::new()
         .init_state::<TabState>()
         .init_state::<InspectWindowState>()
         .add_event::<InspectNet>()
-        .insert_resource(BaseNodes {
-            input_nodes,
-            output_nodes,
-        })
         .add_plugins(DefaultPlugins.set(window_plugin))
         .add_systems(Startup, setup)
         .add_plugins(EguiPlugin)
@@ -88,11 +69,14 @@ fn main() {
 }
 
 fn setup(mut commands: Commands) {
-    commands.spawn(Camera2dBundle {
-        camera: Camera {
-            target: RenderTarget::Window(WindowRef::Primary),
+    commands.spawn((
+        Camera2dBundle {
+            camera: Camera {
+                target: RenderTarget::Window(WindowRef::Primary),
+                ..Default::default()
+            },
             ..Default::default()
         },
-        ..Default::default()
-    });
+        WorldCamera,
+    ));
 }
@@ -0,0 +1,441 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+use engine::{
+    nn::{
+        mutate::{AddNeuron, MutateError},
+        next_innovation,
+        provenance::ProvenanceEvent,
+        BasicNeuron, Edge, GraphEdge, GraphLocation, Net, NeuralGraphError, Neuron, Node,
+    },
+    NeuronInfo,
+};
+use thiserror::Error;
+
+use crate::inputs::{TestNeuronA, TestNeuronB};
+
+use super::{resources::*, systems::InspectWindow};
+
+/// Hidden neuron types a node can be switched between
+const NEURON_TYPES: [&str; 3] = ["BasicNeuron", "TestNeuronA", "TestNeuronB"];
+
+#[derive(Debug, Error)]
+pub enum EditError {
+    #[error("Edges can't lead into an input")]
+    IntoInput,
+    #[error("Edges can't leave an output")]
+    FromOutput,
+    #[error("Edges must lead to a later layer")]
+    Backwards,
+    #[error("The edge would create a cycle")]
+    Cycle,
+    #[error("Only hidden neurons can be changed or deleted")]
+    NotHidden,
+    #[error("{0}")]
+    Graph(#[from] NeuralGraphError),
+    #[error("{0}")]
+    Mutate(#[from] MutateError),
+}
+
+impl Editor {
+    /// Saves the net so the changes that follow can be undone in one step
+    pub fn checkpoint(&mut self, net: &Net) {
+        self.undo.push(net.clone());
+    }
+
+    /// Runs an edit, leaving the net untouched if it fails
+    pub fn apply(&mut self, net: &mut Net, edit: impl FnOnce(&mut Net) -> Result<(), EditError>) {
+        let before = net.clone();
+        match edit(net) {
+            Ok(()) => {
+                self.undo.push(before);
+                self.status = None;
+                self.redraw = true;
+            }
+            Err(e) => {
+                *net = before;
+                self.status = Some(e.to_string());
+            }
+        }
+    }
+
+    pub fn undo(&mut self, net: &mut Net) {
+        if let Some(before) = self.undo.pop() {
+            *net = before;
+            self.status = None;
+            self.redraw = true;
+        }
+    }
+}
+
+fn new_neuron(_type: &str, bias: f32, id: usize) -> Box<dyn Neuron> {
+    match _type {
+        "TestNeuronA" => TestNeuronA::new(bias, id),
+        "TestNeuronB" => TestNeuronB::new(bias, id),
+        _ => Box::new(BasicNeuron::new(bias, id)),
+    }
+}
+
+fn loc(l: GraphLocation) -> String {
+    format!("{},{}", l.layer, l.node)
+}
+
+pub fn connect(net: &mut Net, from: GraphLocation, to: GraphLocation) -> Result<(), EditError> {
+    if to.layer == net.input_layer {
+        return Err(EditError::IntoInput);
+    }
+    if from.layer == net.output_layer {
+        return Err(EditError::FromOutput);
+    }
+    if to.layer <= from.layer {
+        return Err(EditError::Backwards);
+    }
+
+    net.graph.add_edge(
+        from,
+        to,
+        Edge {
+            weight: 1.0,
+            enabled: true,
+            innovation: next_innovation(),
+        },
+    )?;
+    if net.graph.has_cycle(Some(from)) {
+        return Err(EditError::Cycle);
+    }
+    net.record(ProvenanceEvent::EdgeAdded { from, to });
+    Ok(())
+}
+
+fn remove_edge(net: &mut Net, from: GraphLocation, to: GraphLocation) -> Result<(), EditError> {
+    if net.graph.remove_edge(&from, &to) {
+        net.record(ProvenanceEvent::EdgeRemoved { from, to });
+    }
+    Ok(())
+}
+
+fn split_edge(net: &mut Net, from: GraphLocation, edge: GraphEdge) -> Result<(), EditError> {
+    let neuron = Box::new(BasicNeuron::new(0.0, next_innovation()));
+    AddNeuron.run(from, edge, net, neuron)?;
+    Ok(())
+}
+
+fn is_hidden(net: &Net, at: GraphLocation) -> bool {
+    at.layer != net.input_layer && at.layer != net.output_layer
+}
+
+fn delete_neuron(net: &mut Net, at: GraphLocation) -> Result<(), EditError> {
+    if !is_hidden(net, at) {
+        return Err(EditError::NotHidden);
+    }
+    net.graph.removed_node(at);
+    net.record(ProvenanceEvent::NeuronRemoved { at });
+    Ok(())
+}
+
+fn change_type(net: &mut Net, at: GraphLocation, _type: &str) -> Result<(), EditError> {
+    let node = net
+        .graph
+        .get_node_mut(&at)
+        .ok_or(NeuralGraphError::NodeNotFound(at))?;
+    let Node::Neuron(n) = &node.value else {
+        return Err(EditError::NotHidden);
+    };
+    node.value = Node::Neuron(new_neuron(_type, n.bias().unwrap_or(0.0), n.id()));
+    net.record(ProvenanceEvent::Edited {
+        change: format!("{} changed to {_type}", loc(at)),
+    });
+    Ok(())
+}
+
+fn set_edge_enabled(
+    net: &mut Net,
+    from: GraphLocation,
+    to: GraphLocation,
+    enabled: bool,
+) -> Result<(), EditError> {
+    let edge = net
+        .graph
+        .get_edge_mut(&from, &to)
+        .ok_or(NeuralGraphError::NodeNotFound(to))?;
+    edge.value.enabled = enabled;
+    net.record(ProvenanceEvent::Edited {
+        change: format!(
+            "edge {} -> {} {}",
+            loc(from),
+            loc(to),
+            if enabled { "enabled" } else { "disabled" }
+        ),
+    });
+    Ok(())
+}
+
+pub fn editor_window(
+    mut egui_ctx: Query<&mut EguiContext, With<InspectWindow>>,
+    mut editor: ResMut<Editor>,
+    mut nn: ResMut<Nn>,
+    mut apply_net: EventWriter<ApplyNet>,
+) {
+    egui::Window::new("Editor").show(egui_ctx.single_mut().get_mut(), |ui| {
+        let mut active = editor.active;
+        if ui.checkbox(&mut active, "Edit brain").changed() {
+            *editor = Editor {
+                active,
+                ..Default::default()
+            };
+        }
+        if !editor.active {
+            ui.label("Following the selected creature");
+            return;
+        }
+
+        ui.horizontal(|ui| {
+            let undo = egui::Button::new(format!("Undo ({})", editor.undo.len()));
+            if ui.add_enabled(!editor.undo.is_empty(), undo).clicked() {
+                editor.undo(&mut nn.net);
+            }
+            if let Some(creature) = nn.creature {
+                if ui.button(format!("Apply to #{creature}")).clicked() {
+                    apply_net.send(ApplyNet {
+                        creature,
+                        net: nn.net.clone(),
+                    });
+                }
+            }
+        });
+
+        if let Some(from) = editor.connect_from {
+            ui.horizontal(|ui| {
+                ui.label(format!("Click the node to connect {} to", loc(from)));
+                if ui.button("Cancel").clicked() {
+                    editor.connect_from = None;
+                }
+            });
+        }
+        if let Some(status) = &editor.status {
+            ui.label(status);
+        }
+    });
+}
+
+pub fn node_editor(
+    mut egui_ctx: Query<&mut EguiContext, With<InspectWindow>>,
+    mut editor: ResMut<Editor>,
+    mut nn: ResMut<Nn>,
+    inspect_info: Res<InspectInfo>,
+    mut next_node_state: ResMut<NextState<InspectNodeState>>,
+) {
+    let at = inspect_info.0 .0;
+    let Some(node) = nn.net.graph.get_node(&at).cloned() else {
+        next_node_state.set(InspectNodeState::None);
+        return;
+    };
+    let net = &mut nn.net;
+
+    egui::Window::new(format!("Edit layer {} node {}", at.layer, at.node)).show(
+        egui_ctx.single_mut().get_mut(),
+        |ui| {
+            if let Node::Neuron(n) = &node.value {
+                let mut _type = n._type();
+                egui::ComboBox::from_label("Type")
+                    .selected_text(_type)
+                    .show_ui(ui, |ui| {
+                        for t in NEURON_TYPES {
+                            ui.selectable_value(&mut _type, t, t);
+                        }
+                    });
+                if _type != n._type() {
+                    editor.apply(net, |net| change_type(net, at, _type));
+                }
+
+                if let Some(mut bias) = n.bias() {
+                    ui.horizontal(|ui| {
+                        ui.label("Bias");
+                        let r = ui.add(egui::DragValue::new(&mut bias).speed(0.01));
+                        if r.drag_started() || r.gained_focus() {
+                            editor.checkpoint(net);
+                        }
+                        if r.changed() {
+                            if let Some(Node::Neuron(n)) =
+                                net.graph.get_node_mut(&at).map(|n| &mut n.value)
+                            {
+                                n.set_bias(bias);
+                            }
+                            editor.redraw = true;
+                        }
+                        if r.drag_released() || r.lost_focus() {
+                            net.record(ProvenanceEvent::Edited {
+                                change: format!("bias of {} set to {bias}", loc(at)),
+                            });
+                        }
+                    });
+                }
+
+                if ui.button("Delete neuron").clicked() {
+                    editor.apply(net, |net| delete_neuron(net, at));
+                    next_node_state.set(InspectNodeState::None);
+                    return;
+                }
+            } else {
+                ui.label(node.value._type());
+            }
+
+            if at.layer != net.output_layer && ui.button("Connect to...").clicked() {
+                editor.connect_from = Some(at);
+            }
+
+            ui.separator();
+            ui.label("Edges (weight, enabled)");
+            for c in node.connections.iter() {
+                ui.horizontal(|ui| {
+                    ui.label(format!("-> {}", loc(c.to)));
+
+                    let mut weight = c.value.weight;
+                    let r = ui.add(egui::DragValue::new(&mut weight).speed(0.01));
+                    if r.drag_started() || r.gained_focus() {
+                        editor.checkpoint(net);
+                    }
+                    if r.changed() {
+                        if let Some(e) = net.graph.get_edge_mut(&at, &c.to) {
+                            e.value.weight = weight;
+                        }
+                        editor.redraw = true;
+                    }
+                    if r.drag_released() || r.lost_focus() {
+                        net.record(ProvenanceEvent::Edited {
+                            change: format!(
+                                "weight of {} -> {} set to {weight}",
+                                loc(at),
+                                loc(c.to)
+                            ),
+                        });
+                    }
+
+                    let mut enabled = c.value.enabled;
+                    if ui.checkbox(&mut enabled, "").changed() {
+                        editor.apply(net, |net| set_edge_enabled(net, at, c.to, enabled));
+                    }
+                    if ui.small_button("Split").clicked() {
+                        editor.apply(net, |net| split_edge(net, at, *c));
+                    }
+                    if ui.small_button("x").clicked() {
+                        editor.apply(net, |net| remove_edge(net, at, c.to));
+                    }
+                });
+            }
+        },
+    );
+}
+
+#[cfg(test)]
+mod test {
+    use engine::{
+        activations::Sigmoid,
+        nn::{BasicNeuron, GraphLocation, GraphNode, GraphSize, Net, NeuralGraph, Node},
+    };
+
+    use super::{connect, delete_neuron, EditError, Editor};
+    use crate::inputs::Hunger;
+
+    /// Two inputs, two hidden neurons and an output, without edges
+    fn net() -> Net {
+        let mut graph = NeuralGraph::new();
+        let mut id = 0;
+        for (l, size) in [2, 2, 1].into_iter().enumerate() {
+            let layer = graph.add_layer_to_end();
+            for _ in 0..size {
+                let node = match l {
+                    0 => Node::Input(Hunger::new(0.0, id)),
+                    2 => Node::Output(Sigmoid::new(0.0, id, "out".to_owned())),
+                    _ => Node::Neuron(Box::new(BasicNeuron::new(0.0, id))),
+                };
+                graph.add_node(layer, GraphNode::new(node)).unwrap();
+                id += 1;
+            }
+        }
+        Net {
+            graph,
+            input_layer: 0,
+            output_layer: 2,
+            ..Default::default()
+        }
+    }
+
+    fn at(layer: GraphSize, node: GraphSize) -> GraphLocation {
+        GraphLocation::new(layer, node)
+    }
+
+    fn bytes(net: &Net) -> Vec<u8> {
+        net.to_bytes().unwrap()
+    }
+
+    #[test]
+    fn connect_checks_direction() {
+        let mut n = net();
+        assert!(matches!(
+            connect(&mut n, at(1, 0), at(0, 0)),
+            Err(EditError::IntoInput)
+        ));
+        assert!(matches!(
+            connect(&mut n, at(2, 0), at(1, 0)),
+            Err(EditError::FromOutput)
+        ));
+        assert!(matches!(
+            connect(&mut n, at(1, 0), at(1, 1)),
+            Err(EditError::Backwards)
+        ));
+        assert!(n.graph.get_edge(&at(0, 0), &at(2, 0)).is_none());
+
+        connect(&mut n, at(0, 0), at(2, 0)).unwrap();
+        assert!(n.graph.get_edge(&at(0, 0), &at(2, 0)).is_some());
+    }
+
+    #[test]
+    fn failed_edit_restores_the_net() {
+        let mut n = net();
+        let before = bytes(&n);
+        let mut editor = Editor::default();
+        // changes the net before failing
+        editor.apply(&mut n, |net| {
+            delete_neuron(net, at(1, 0))?;
+            Err(EditError::Cycle)
+        });
+        assert_eq!(bytes(&n), before);
+        assert_eq!(editor.status, Some(EditError::Cycle.to_string()));
+        assert!(editor.undo.is_empty());
+
+        editor.apply(&mut n, |net| connect(net, at(0, 0), at(1, 0)));
+        assert_eq!(editor.status, None);
+    }
+
+    #[test]
+    fn undo_restores_the_net() {
+        let mut n = net();
+        let before = bytes(&n);
+        let mut editor = Editor::default();
+        editor.apply(&mut n, |net| connect(net, at(0, 0), at(1, 0)));
+        editor.apply(&mut n, |net| delete_neuron(net, at(1, 1)));
+        assert_eq!(editor.undo.len(), 2);
+
+        editor.undo(&mut n);
+        assert_eq!(n.graph.layers[1].len(), 2);
+        editor.undo(&mut n);
+        assert_eq!(bytes(&n), before);
+        assert!(editor.undo.is_empty());
+    }
+
+    #[test]
+    fn only_hidden_neurons_are_deleted() {
+        let mut n = net();
+        for l in [0, 2] {
+            assert!(matches!(
+                delete_neuron(&mut n, at(l, 0)),
+                Err(EditError::NotHidden)
+            ));
+        }
+        assert_eq!(n.graph.layers[0].len(), 2);
+        assert_eq!(n.graph.layers[2].len(), 1);
+
+        delete_neuron(&mut n, at(1, 0)).unwrap();
+        assert_eq!(n.graph.layers[1].len(), 1);
+    }
+}
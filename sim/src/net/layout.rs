@@ -0,0 +1,285 @@
+use std::collections::HashMap;
+
+use bevy::math::Vec2;
+use engine::nn::{GraphLocation, GraphSize, Net};
+
+const LAYER_SPACING: f32 = 160.0;
+const NODE_SPACING: f32 = 60.0;
+/// Barycenter passes, each goes down the layers and back up once
+const SWEEPS: usize = 8;
+
+/// Entry in a layer's ordering, dummies carry edges skipping layers through the ones in between
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Slot {
+    Node(GraphLocation),
+    /// Index of the edge and the layer it passes
+    Dummy(usize, GraphSize),
+}
+
+impl Slot {
+    fn layer(self) -> usize {
+        match self {
+            Slot::Node(l) => l.layer as usize,
+            Slot::Dummy(_, layer) => layer as usize,
+        }
+    }
+}
+
+/// Where the inspector draws every node and the points every edge is routed through
+#[derive(Debug, Default)]
+pub struct NetLayout {
+    pub nodes: HashMap<GraphLocation, Vec2>,
+    /// Source, target and the points from one to the other
+    pub edges: Vec<(GraphLocation, GraphLocation, Vec<Vec2>)>,
+}
+
+/// Sugiyama style layout: the net's layers are the ranks, edges skipping layers get a dummy
+/// in every layer they pass and the orderings are sorted by barycenter to reduce crossings
+pub fn layout(net: &Net) -> NetLayout {
+    let layers = &net.graph.layers;
+    let mut order = layers
+        .iter()
+        .enumerate()
+        .map(|(l, layer)| {
+            (0..layer.len())
+                .map(|n| Slot::Node(GraphLocation::new(l as GraphSize, n as GraphSize)))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    let edges = layers
+        .iter()
+        .enumerate()
+        .flat_map(|(l, layer)| {
+            layer.iter().enumerate().flat_map(move |(n, node)| {
+                node.connections
+                    .iter()
+                    .map(move |c| (GraphLocation::new(l as GraphSize, n as GraphSize), c.to))
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let mut chains = Vec::new();
+    for (idx, (from, to)) in edges.iter().enumerate() {
+        let mut chain = vec![Slot::Node(*from)];
+        for layer in from.layer + 1..to.layer {
+            let dummy = Slot::Dummy(idx, layer);
+            order[layer as usize].push(dummy);
+            chain.push(dummy);
+        }
+        chain.push(Slot::Node(*to));
+        chains.push(chain);
+    }
+
+    // neighbours in the layer before and after every slot
+    let mut up: HashMap<Slot, Vec<Slot>> = HashMap::new();
+    let mut down: HashMap<Slot, Vec<Slot>> = HashMap::new();
+    for chain in chains.iter() {
+        for w in chain.windows(2) {
+            if w[1].layer() == w[0].layer() + 1 {
+                down.entry(w[0]).or_default().push(w[1]);
+                up.entry(w[1]).or_default().push(w[0]);
+            }
+        }
+    }
+
+    let mut best = order.clone();
+    let mut best_crossings = crossings(&order, &down);
+    for _ in 0..SWEEPS {
+        for l in 1..order.len() {
+            let (fixed, free) = order.split_at_mut(l);
+            sort_by_barycenter(&mut free[0], &fixed[l - 1], &up);
+        }
+        for l in (0..order.len().saturating_sub(1)).rev() {
+            let (free, fixed) = order.split_at_mut(l + 1);
+            sort_by_barycenter(&mut free[l], &fixed[0], &down);
+        }
+
+        let c = crossings(&order, &down);
+        if c < best_crossings {
+            best_crossings = c;
+            best = order.clone();
+        }
+        if best_crossings == 0 {
+            break;
+        }
+    }
+
+    let positions = place(&best);
+    NetLayout {
+        nodes: positions
+            .iter()
+            .filter_map(|(slot, p)| match slot {
+                Slot::Node(l) => Some((*l, *p)),
+                Slot::Dummy(..) => None,
+            })
+            .collect(),
+        edges: edges
+            .iter()
+            .zip(chains.iter())
+            .map(|((from, to), chain)| (*from, *to, chain.iter().map(|s| positions[s]).collect()))
+            .collect(),
+    }
+}
+
+fn sort_by_barycenter(free: &mut [Slot], fixed: &[Slot], neighbours: &HashMap<Slot, Vec<Slot>>) {
+    let pos = index(fixed);
+    let mut keyed = free
+        .iter()
+        .enumerate()
+        .map(|(i, s)| {
+            let key = match neighbours.get(s) {
+                Some(n) if !n.is_empty() => {
+                    n.iter().map(|x| pos[x] as f32).sum::<f32>() / n.len() as f32
+                }
+                // nodes without neighbours keep their place
+                _ => i as f32,
+            };
+            (key, *s)
+        })
+        .collect::<Vec<_>>();
+    keyed.sort_by(|a, b| a.0.total_cmp(&b.0));
+    for (slot, (_, s)) in free.iter_mut().zip(keyed) {
+        *slot = s;
+    }
+}
+
+fn index(layer: &[Slot]) -> HashMap<Slot, usize> {
+    layer.iter().enumerate().map(|(i, s)| (*s, i)).collect()
+}
+
+fn crossings(order: &[Vec<Slot>], down: &HashMap<Slot, Vec<Slot>>) -> usize {
+    let mut count = 0;
+    for w in order.windows(2) {
+        let (top, bottom) = (index(&w[0]), index(&w[1]));
+        let segments = w[0]
+            .iter()
+            .flat_map(|s| {
+                let (top, bottom) = (&top, &bottom);
+                down.get(s)
+                    .into_iter()
+                    .flatten()
+                    .map(move |t| (top[s], bottom[t]))
+            })
+            .collect::<Vec<_>>();
+        for (i, a) in segments.iter().enumerate() {
+            for b in segments[i + 1..].iter() {
+                if (a.0 < b.0 && a.1 > b.1) || (a.0 > b.0 && a.1 < b.1) {
+                    count += 1;
+                }
+            }
+        }
+    }
+    count
+}
+
+/// Layers left to right and every layer centered vertically around the origin
+fn place(order: &[Vec<Slot>]) -> HashMap<Slot, Vec2> {
+    let half_width = order.len().saturating_sub(1) as f32 / 2.0;
+    order
+        .iter()
+        .enumerate()
+        .flat_map(|(l, layer)| {
+            let half_height = layer.len().saturating_sub(1) as f32 / 2.0;
+            layer.iter().enumerate().map(move |(i, s)| {
+                (
+                    *s,
+                    Vec2::new(
+                        (l as f32 - half_width) * LAYER_SPACING,
+                        (half_height - i as f32) * NODE_SPACING,
+                    ),
+                )
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use engine::{
+        activations::Sigmoid,
+        nn::{BasicNeuron, Edge, GraphLocation, GraphNode, GraphSize, Net, NeuralGraph, Node},
+    };
+
+    use super::{layout, NetLayout, LAYER_SPACING};
+    use crate::inputs::Hunger;
+
+    /// Layer and node of the source and the target
+    type Link = ((GraphSize, GraphSize), (GraphSize, GraphSize));
+
+    /// A net with `sizes[l]` nodes in layer `l` and the given edges
+    fn net(sizes: &[usize], edges: &[Link]) -> Net {
+        let mut graph = NeuralGraph::new();
+        let last = sizes.len() - 1;
+        let mut id = 0;
+        for (l, size) in sizes.iter().enumerate() {
+            let layer = graph.add_layer_to_end();
+            for _ in 0..*size {
+                let node = match l {
+                    0 => Node::Input(Hunger::new(0.0, id)),
+                    l if l == last => Node::Output(Sigmoid::new(0.0, id, "out".to_owned())),
+                    _ => Node::Neuron(Box::new(BasicNeuron::new(0.0, id))),
+                };
+                graph.add_node(layer, GraphNode::new(node)).unwrap();
+                id += 1;
+            }
+        }
+        for (from, to) in edges {
+            graph
+                .add_edge(
+                    GraphLocation::new(from.0, from.1),
+                    GraphLocation::new(to.0, to.1),
+                    Edge::default(),
+                )
+                .unwrap();
+        }
+        Net {
+            graph,
+            input_layer: 0,
+            output_layer: last as GraphSize,
+            ..Default::default()
+        }
+    }
+
+    fn y(layout: &NetLayout, l: GraphSize, n: GraphSize) -> f32 {
+        layout.nodes[&GraphLocation::new(l, n)].y
+    }
+
+    #[test]
+    fn places_every_node_in_its_layer() {
+        let layout = layout(&net(&[3, 2, 1], &[((0, 0), (1, 0)), ((1, 1), (2, 0))]));
+        assert_eq!(layout.nodes.len(), 6);
+        for (location, p) in layout.nodes.iter() {
+            let x = (location.layer as f32 - 1.0) * LAYER_SPACING;
+            assert_eq!(p.x, x);
+        }
+        assert_eq!(y(&layout, 2, 0), 0.0);
+    }
+
+    #[test]
+    fn uncrosses_edges() {
+        let layout = layout(&net(&[2, 2], &[((0, 0), (1, 1)), ((0, 1), (1, 0))]));
+        let inputs = y(&layout, 0, 0) - y(&layout, 0, 1);
+        let outputs = y(&layout, 1, 1) - y(&layout, 1, 0);
+        assert!(inputs * outputs > 0.0);
+    }
+
+    #[test]
+    fn routes_edges_through_skipped_layers() {
+        let layout = layout(&net(
+            &[1, 1, 1],
+            &[((0, 0), (1, 0)), ((1, 0), (2, 0)), ((0, 0), (2, 0))],
+        ));
+        let (_, _, points) = layout
+            .edges
+            .iter()
+            .find(|(from, to, _)| from.layer == 0 && to.layer == 2)
+            .unwrap();
+        assert_eq!(points.len(), 3);
+        assert_eq!(points[0], layout.nodes[&GraphLocation::new(0, 0)]);
+        assert_eq!(points[1].x, 0.0);
+        // the dummy sits next to the hidden node instead of on it
+        assert_ne!(points[1], layout.nodes[&GraphLocation::new(1, 0)]);
+        assert_eq!(points[2], layout.nodes[&GraphLocation::new(2, 0)]);
+    }
+}
@@ -2,8 +2,10 @@ use bevy::prelude::*;
 
 use crate::InspectWindowState;
 
-use self::{resources::*, systems::*};
+use self::{editor::*, resources::*, systems::*};
 
+mod editor;
+mod layout;
 pub mod resources;
 mod systems;
 
@@ -20,6 +22,8 @@ impl Plugin for NeuralNetPlugin {
         app.init_resource::<InspectInfo>()
             .init_resource::<WindowInfo>()
             .init_resource::<ControlPanel>()
+            .init_resource::<Editor>()
+            .add_event::<ApplyNet>()
             .init_state::<InspectNodeState>()
             .add_systems(OnEnter(InspectWindowState::Display), setup)
             .add_systems(OnExit(InspectWindowState::Display), exit_inspector)
@@ -30,18 +34,40 @@ impl Plugin for NeuralNetPlugin {
             .configure_sets(Update, IwSet::Events.before(IwSet::Window))
             .add_systems(
                 Update,
-                toggle_inspect_window
+                (toggle_inspect_window, inspector_camera)
                     .run_if(in_state(InspectWindowState::Display))
                     .run_if(resource_exists::<Nn>)
                     .in_set(IwSet::Events),
             )
+            .add_systems(
+                Update,
+                history_window
+                    .run_if(in_state(InspectWindowState::Display))
+                    .in_set(IwSet::Window)
+                    .run_if(resource_exists::<Nn>),
+            )
             .add_systems(
                 Update,
                 inspect_window
                     .run_if(in_state(InspectWindowState::Display))
                     .in_set(IwSet::Window)
                     .run_if(resource_exists::<Nn>)
-                    .run_if(in_state(InspectNodeState::Display)),
+                    .run_if(in_state(InspectNodeState::Display))
+                    .run_if(|editor: Res<Editor>| !editor.active),
+            )
+            .add_systems(
+                Update,
+                (
+                    editor_window,
+                    node_editor
+                        .run_if(in_state(InspectNodeState::Display))
+                        .run_if(|editor: Res<Editor>| editor.active),
+                    redraw_net,
+                )
+                    .chain()
+                    .run_if(in_state(InspectWindowState::Display))
+                    .in_set(IwSet::Window)
+                    .run_if(resource_exists::<Nn>),
             );
     }
 }
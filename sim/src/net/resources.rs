@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::{collections::HashMap, path::PathBuf};
 
 use bevy::prelude::*;
 use egui_file::FileDialog;
@@ -8,8 +8,16 @@ use serde::{Deserialize, Serialize};
 #[derive(Resource, Debug)]
 pub struct InspectorWindowId(pub Entity);
 
+/// Net to show in the inspector and the creature it belongs to, if any
 #[derive(Event)]
-pub struct InspectNet(pub Net);
+pub struct InspectNet(pub Net, pub Option<usize>);
+
+/// Edited brain to put back into its creature
+#[derive(Event)]
+pub struct ApplyNet {
+    pub creature: usize,
+    pub net: Net,
+}
 
 #[derive(States, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
 pub enum InspectNodeState {
@@ -27,7 +35,22 @@ pub struct NodePosition {
 #[derive(Resource, Debug, Default, Serialize, Deserialize)]
 pub struct Nn {
     pub net: Net,
+    pub creature: Option<usize>,
     pub node_positions: Vec<(GraphLocation, NodePosition)>,
+    /// Node outputs during the net's last tick
+    pub activations: HashMap<GraphLocation, f32>,
+}
+
+/// Hand edits of the inspected brain, following the creature pauses while editing
+#[derive(Resource, Debug, Default)]
+pub struct Editor {
+    pub active: bool,
+    /// Nets as they were before every edit, newest last
+    pub undo: Vec<Net>,
+    /// Node the next clicked node gets connected from
+    pub connect_from: Option<GraphLocation>,
+    pub redraw: bool,
+    pub status: Option<String>,
 }
 
 #[derive(Resource, Debug, Default)]
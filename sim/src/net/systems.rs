@@ -1,4 +1,5 @@
 use bevy::{
+    input::mouse::{MouseMotion, MouseWheel},
     prelude::*,
     render::{camera::RenderTarget, view::RenderLayers},
     window::WindowRef,
@@ -8,16 +9,33 @@ use bevy_egui::{
     EguiContext,
 };
 use bevy_vector_shapes::prelude::*;
-use engine::nn::{GraphLocation, Net};
+use engine::{
+    nn::{Net, Node},
+    NeuronInfo,
+};
 
-use super::resources::*;
+use super::{editor::connect, layout::layout, resources::*};
 
 const CIRCLE_RADIUS: f32 = 20.0;
-const SPACING: f32 = 10.0;
+const QUIET_COLOR: &str = "808080";
+const POSITIVE_COLOR: &str = "54ff71";
+const NEGATIVE_COLOR: &str = "eb4034";
+const DISABLED_COLOR: &str = "1b1b1b";
+const MAX_EDGE_THICKNESS: f32 = 8.0;
+const LABEL_SIZE: f32 = 14.0;
+const MIN_ZOOM: f32 = 0.1;
+const MAX_ZOOM: f32 = 20.0;
 
 #[derive(Component)]
 pub struct InspectWindow;
 
+#[derive(Component)]
+pub struct InspectCamera;
+
+/// Everything drawn for the inspected net, cleared on every redraw
+#[derive(Component)]
+pub struct NetShape;
+
 pub fn setup(mut commands: Commands) {
     let inspect_net_window = commands
         .spawn((
@@ -40,137 +58,182 @@ pub fn setup(mut commands: Commands) {
             ..Default::default()
         },
         render_layer,
+        InspectCamera,
     ));
     commands.insert_resource(InspectorWindowId(inspect_net_window));
 }
 
-pub fn exit_inspector(mut commands: Commands, inspector_window_id: Res<InspectorWindowId>) {
+pub fn exit_inspector(
+    mut commands: Commands,
+    inspector_window_id: Res<InspectorWindowId>,
+    cameras: Query<Entity, With<InspectCamera>>,
+) {
     commands.entity(inspector_window_id.0).despawn_recursive();
+    for camera in cameras.iter() {
+        commands.entity(camera).despawn_recursive();
+    }
 }
 
 pub fn get_inspect_net(
     mut shapes: ShapeCommands,
     mut commands: Commands,
     mut event_reader: EventReader<InspectNet>,
-    window: Query<&Window, With<InspectWindow>>,
-    circles: Query<Entity, (With<DiscComponent>, With<InspectWindow>)>,
-    lines: Query<Entity, (With<LineComponent>, With<InspectWindow>)>,
+    drawn: Query<Entity, With<NetShape>>,
+    mut inspect_info: ResMut<InspectInfo>,
+    editor: Res<Editor>,
 ) {
-    let w = window.get_single().unwrap();
-    let dims = (w.width(), w.height());
-    for item in event_reader.read() {
-        let nn = draw_neural_net(
-            item.0.clone(),
-            &circles,
-            &lines,
-            dims,
-            &mut commands,
-            &mut shapes,
-        );
+    // only the newest net matters when following a creature, edits would be lost
+    let Some(item) = event_reader.read().last() else {
+        return;
+    };
+    if editor.active {
+        return;
+    }
+
+    let mut nn = draw_neural_net(item.0.clone(), &drawn, &mut commands, &mut shapes);
+    nn.creature = item.1;
 
-        commands.insert_resource(nn);
-        break;
+    // keep the node window showing the live node
+    if let Some(node) = nn.net.graph.get_node(&inspect_info.0 .0) {
+        inspect_info.0 .1 = node.clone();
     }
+    commands.insert_resource(nn);
 }
 
-fn x_pos(layer: usize) -> f32 {
-    ((CIRCLE_RADIUS * 2.0) + SPACING) * layer as f32
+/// Grey when quiet, fading to green for positive and red for negative values
+fn signed_color(value: f32) -> Color {
+    let quiet = Color::hex(QUIET_COLOR).unwrap().as_rgba_f32();
+    let target = Color::hex(if value >= 0.0 {
+        POSITIVE_COLOR
+    } else {
+        NEGATIVE_COLOR
+    })
+    .unwrap()
+    .as_rgba_f32();
+    // squashed so large inputs like age don't all look the same
+    let t = value.abs().tanh();
+    let lerp = |i: usize| quiet[i] + (target[i] - quiet[i]) * t;
+    Color::rgb(lerp(0), lerp(1), lerp(2))
 }
 
-fn y_pos(node: usize) -> f32 {
-    ((CIRCLE_RADIUS * 2.0) + SPACING) * node as f32
+/// Input and hidden neurons by type and id, outputs by what they control
+fn label(node: &Node) -> String {
+    match node {
+        Node::Output(o) => o.name().unwrap_or(o._type()).to_owned(),
+        Node::None => String::new(),
+        n => format!("{} #{}", n._type(), n.id()),
+    }
 }
 
 pub fn draw_neural_net(
     net: Net,
-    circles: &Query<Entity, (With<DiscComponent>, With<InspectWindow>)>,
-    lines: &Query<Entity, (With<LineComponent>, With<InspectWindow>)>,
-    dims: (f32, f32),
+    drawn: &Query<Entity, With<NetShape>>,
     commands: &mut Commands,
     shapes: &mut ShapeCommands,
 ) -> Nn {
-    for item in circles.iter() {
-        commands.entity(item).despawn_recursive();
-    }
-    for item in lines.iter() {
+    for item in drawn.iter() {
         commands.entity(item).despawn_recursive();
     }
 
     let render_layer = RenderLayers::layer(1);
-    let mut nodes = Vec::new();
-
-    shapes.color = Color::hex("1b1b1b").unwrap();
-    for (num, layer) in net.graph.layers.iter().enumerate() {
-        let count = layer.iter().count();
-        let start_x = (-1.0 * dims.0 / 3.0) + ((CIRCLE_RADIUS * 2.0) + SPACING) * num as f32;
-        let start_y = -1.0
-            * (((CIRCLE_RADIUS * 2.0 * count as f32) + (SPACING * (count as f32 - 1.0))) / 2.0);
-        for (node_num, node) in layer.iter().enumerate() {
-            shapes.transform =
-                Transform::from_xyz(start_x + x_pos(num), start_y + y_pos(node_num), 0.0);
-            nodes.push((
-                GraphLocation {
-                    layer: num as u16,
-                    node: node_num as u16,
-                },
-                NodePosition {
-                    x: start_x + x_pos(num),
-                    y: start_y + y_pos(node_num),
-                },
-            ));
+    let activations = net.activations();
+    let layout = layout(&net);
+
+    for (location, position) in layout.nodes.iter() {
+        shapes.transform = Transform::from_xyz(position.x, position.y, 0.0);
+        shapes.color = activations
+            .get(location)
+            .map_or(Color::hex(QUIET_COLOR).unwrap(), |v| signed_color(*v));
+        commands.spawn((
+            ShapeBundle::circle(shapes.config(), CIRCLE_RADIUS),
+            InspectWindow,
+            NetShape,
+            render_layer,
+        ));
+
+        let Some(node) = net.graph.get_node(location) else {
+            continue;
+        };
+        commands.spawn((
+            Text2dBundle {
+                text: Text::from_section(
+                    label(&node.value),
+                    TextStyle {
+                        font_size: LABEL_SIZE,
+                        color: Color::WHITE,
+                        ..Default::default()
+                    },
+                ),
+                transform: Transform::from_xyz(
+                    position.x,
+                    position.y - CIRCLE_RADIUS - LABEL_SIZE,
+                    1.0,
+                ),
+                ..Default::default()
+            },
+            InspectWindow,
+            NetShape,
+            render_layer,
+        ));
+    }
+
+    for (from, to, points) in layout.edges.iter() {
+        let Some(edge) = net.graph.get_edge(from, to) else {
+            continue;
+        };
+        if edge.value.enabled {
+            shapes.color = signed_color(edge.value.weight);
+            shapes.thickness = 1.0 + (MAX_EDGE_THICKNESS - 1.0) * edge.value.weight.abs().tanh();
+        } else {
+            shapes.color = Color::hex(DISABLED_COLOR).unwrap();
+            shapes.thickness = 1.0;
+        }
+
+        // edges skipping layers bend through the dummy positions between them
+        shapes.set_translation(Vec3::NEG_Z);
+        for segment in points.windows(2) {
             commands.spawn((
-                ShapeBundle::circle(shapes.config(), CIRCLE_RADIUS),
+                ShapeBundle::line(
+                    shapes.config(),
+                    segment[0].extend(-1.0),
+                    segment[1].extend(-1.0),
+                ),
                 InspectWindow,
+                NetShape,
                 render_layer,
             ));
-
-            for c in node.connections.iter() {
-                let to_x =
-                    (-1.0 * dims.0 / 3.0) + ((CIRCLE_RADIUS * 2.0) + SPACING) * c.to.layer as f32;
-                let to_layer_count = net
-                    .graph
-                    .layers
-                    .iter()
-                    .skip(c.to.layer as usize)
-                    .next()
-                    .unwrap()
-                    .len();
-                let to_y: f32 = -1.0
-                    * (((CIRCLE_RADIUS * 2.0 * to_layer_count as f32)
-                        + (SPACING * (to_layer_count as f32 - 1.0)))
-                        / 2.0);
-
-                if c.value.enabled {
-                    shapes.color = Color::hex("1b1b1b").unwrap();
-                } else {
-                    shapes.color = Color::hex("808080").unwrap();
-                }
-
-                shapes.thickness = 5.0;
-                shapes.set_translation(Vec3::NEG_Z);
-                commands.spawn((
-                    ShapeBundle::line(
-                        shapes.config(),
-                        Vec3::new(start_x + x_pos(num), start_y + y_pos(node_num), -1.0),
-                        Vec3::new(
-                            to_x + x_pos(c.to.layer as usize),
-                            to_y + y_pos(c.to.node as usize),
-                            -1.0,
-                        ),
-                    ),
-                    InspectWindow,
-                    render_layer,
-                ));
-                shapes.thickness = 0.0;
-                shapes.color = Color::hex("1b1b1b").unwrap();
-            }
         }
+        shapes.thickness = 0.0;
     }
 
     Nn {
         net,
-        node_positions: nodes,
+        creature: None,
+        node_positions: layout
+            .nodes
+            .iter()
+            .map(|(l, p)| (*l, NodePosition { x: p.x, y: p.y }))
+            .collect(),
+        activations,
+    }
+}
+
+/// Redraws the inspected net after it was edited
+pub fn redraw_net(
+    mut shapes: ShapeCommands,
+    mut commands: Commands,
+    mut editor: ResMut<Editor>,
+    nn: Res<Nn>,
+    drawn: Query<Entity, With<NetShape>>,
+) {
+    if !editor.redraw {
+        return;
     }
+    editor.redraw = false;
+
+    let mut redrawn = draw_neural_net(nn.net.clone(), &drawn, &mut commands, &mut shapes);
+    redrawn.creature = nn.creature;
+    commands.insert_resource(redrawn);
 }
 
 // pub fn draw_neural_net(
@@ -276,25 +339,39 @@ pub fn draw_neural_net(
 pub fn toggle_inspect_window(
     buttons: Res<ButtonInput<MouseButton>>,
     q_windows: Query<&Window, With<InspectWindow>>,
-    data: Res<Nn>,
+    camera: Query<(&Camera, &GlobalTransform), With<InspectCamera>>,
+    mut egui_ctx: Query<&mut EguiContext, With<InspectWindow>>,
+    mut data: ResMut<Nn>,
     keyboard: Res<ButtonInput<KeyCode>>,
     mut next_iw_state: ResMut<NextState<InspectNodeState>>,
     mut inspect_info: ResMut<InspectInfo>,
+    mut editor: ResMut<Editor>,
 ) {
     if keyboard.just_pressed(KeyCode::Escape) {
         next_iw_state.set(InspectNodeState::None);
+        editor.connect_from = None;
         return;
     }
 
-    if buttons.just_pressed(MouseButton::Left) {
-        let w = q_windows.single();
-        if let Some(position) = w.cursor_position() {
-            let x = position.x - w.width() / 2.0;
-            let y = (position.y - w.height() / 2.0) * -1.0;
+    if buttons.just_pressed(MouseButton::Left)
+        && !egui_ctx.single_mut().get_mut().is_pointer_over_area()
+    {
+        let (camera, camera_transform) = camera.single();
+        if let Some(position) = q_windows
+            .single()
+            .cursor_position()
+            .and_then(|p| camera.viewport_to_world_2d(camera_transform, p))
+        {
+            let (x, y) = (position.x, position.y);
 
             for node in &data.node_positions {
                 let pos = (x - node.1.x).powi(2) + (y - node.1.y).powi(2);
                 if pos <= CIRCLE_RADIUS.powi(2) {
+                    if let Some(from) = editor.connect_from.take() {
+                        let to = node.0;
+                        editor.apply(&mut data.net, |net| connect(net, from, to));
+                        break;
+                    }
                     inspect_info.0 .0 = node.0.clone();
                     inspect_info.0 .1 = data.net.graph.get_node(&node.0).unwrap().clone();
                     next_iw_state.set(InspectNodeState::Display);
@@ -305,9 +382,61 @@ pub fn toggle_inspect_window(
     }
 }
 
+/// Drag with the right mouse button to pan, scroll to zoom and press F to fit the net
+/// in the window, which also happens whenever another creature is inspected
+pub fn inspector_camera(
+    mut camera: Query<(&mut Transform, &mut OrthographicProjection), With<InspectCamera>>,
+    window: Query<(Entity, &Window), With<InspectWindow>>,
+    mut egui_ctx: Query<&mut EguiContext, With<InspectWindow>>,
+    mut wheel: EventReader<MouseWheel>,
+    mut motion: EventReader<MouseMotion>,
+    buttons: Res<ButtonInput<MouseButton>>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    nn: Res<Nn>,
+    mut last_creature: Local<Option<Option<usize>>>,
+) {
+    let (entity, w) = window.single();
+    let (mut transform, mut projection) = camera.single_mut();
+
+    if keyboard.just_pressed(KeyCode::KeyF) || *last_creature != Some(nn.creature) {
+        *last_creature = Some(nn.creature);
+        let (min, max) = nn.node_positions.iter().fold(
+            (Vec2::splat(f32::MAX), Vec2::splat(f32::MIN)),
+            |(min, max), (_, p)| {
+                let p = Vec2::new(p.x, p.y);
+                (min.min(p), max.max(p))
+            },
+        );
+        if min.x <= max.x {
+            let size = max - min + Vec2::splat(CIRCLE_RADIUS * 4.0);
+            projection.scale = (size.x / w.width())
+                .max(size.y / w.height())
+                .clamp(MIN_ZOOM, MAX_ZOOM);
+            transform.translation = ((min + max) / 2.0).extend(transform.translation.z);
+        }
+    }
+
+    let hovered = w.cursor_position().is_some();
+    let over_egui = egui_ctx.single_mut().get_mut().is_pointer_over_area();
+    for event in wheel.read() {
+        if event.window != entity || over_egui {
+            continue;
+        }
+        let factor = if event.y > 0.0 { 0.9 } else { 1.1 };
+        projection.scale = (projection.scale * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+    }
+
+    let delta = motion.read().map(|m| m.delta).sum::<Vec2>();
+    if hovered && buttons.pressed(MouseButton::Right) {
+        transform.translation.x -= delta.x * projection.scale;
+        transform.translation.y += delta.y * projection.scale;
+    }
+}
+
 pub fn inspect_window(
     mut egui_ctx: Query<&mut EguiContext, With<InspectWindow>>,
     inspect_info: Res<InspectInfo>,
+    data: Res<Nn>,
     mut window_state: ResMut<WindowInfo>,
 ) {
     let mut style = (*egui_ctx.single_mut().get_mut().style()).clone();
@@ -325,10 +454,38 @@ pub fn inspect_window(
             *style.text_styles.get_mut(&egui::TextStyle::Body).unwrap() =
                 egui::FontId::new(20.0, egui::FontFamily::Proportional);
             ui.set_style(style);
-            ui.label(format!("{:#?}", inspect_info.0 .1));
+            ui.vertical(|ui| {
+                match data.activations.get(&inspect_info.0 .0) {
+                    Some(v) => ui.label(format!("Activation: {v}")),
+                    None => ui.label("Not reached last tick"),
+                };
+                ui.label(format!("{:#?}", inspect_info.0 .1));
+            });
         });
     })
     .unwrap();
 
     window_state.inspect_window_pos = (window.response.rect.left(), window.response.rect.top());
 }
+
+pub fn history_window(mut egui_ctx: Query<&mut EguiContext, With<InspectWindow>>, data: Res<Nn>) {
+    egui::Window::new("History")
+        .default_open(false)
+        .show(egui_ctx.single_mut().get_mut(), |ui| {
+            match &data.net.provenance {
+                Some(events) if !events.is_empty() => {
+                    egui::ScrollArea::vertical().show(ui, |ui| {
+                        for (idx, event) in events.iter().enumerate() {
+                            ui.label(format!("{idx}: {event}"));
+                        }
+                    });
+                }
+                Some(_) => {
+                    ui.label("No mutations yet");
+                }
+                None => {
+                    ui.label("History is not recorded for this net");
+                }
+            }
+        });
+}
@@ -1,12 +1,8 @@
 use serde::Serialize;
 
 #[derive(Debug, Clone, Serialize)]
-pub struct Forward;
+pub struct Thrust;
 #[derive(Debug, Clone, Serialize)]
-pub struct Backward;
+pub struct Turn;
 #[derive(Debug, Clone, Serialize)]
-pub struct Left;
-#[derive(Debug, Clone, Serialize)]
-pub struct Right;
-#[derive(Debug, Clone, Serialize)]
-pub struct OutputSpeed;
+pub struct Attack;
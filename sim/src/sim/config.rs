@@ -0,0 +1,328 @@
+use std::{fs, path::Path};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::{
+    food::FoodConfig,
+    recorder::RecordConfig,
+    sensors::SensorConfig,
+    species::{Species, Template, TemplateError},
+    world::{Boundary, WorldMap},
+};
+
+/// Smallest world side creatures are still placed in
+pub const MIN_WORLD_DIM: f32 = 100.0;
+
+/// Starting points for the creature count and world size
+pub const PRESETS: [(&str, usize, (f32, f32)); 3] = [
+    ("Small", 100, (640.0, 360.0)),
+    ("Medium", 500, (1280.0, 720.0)),
+    ("Large", 2000, (2560.0, 1440.0)),
+];
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("Could not access experiment file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Could not parse experiment file: {0}")]
+    Parse(#[from] ron::error::SpannedError),
+    #[error("Could not write experiment file: {0}")]
+    Write(#[from] ron::Error),
+}
+
+/// Settings a simulation can't be created from
+#[derive(Debug, Error)]
+pub enum InvalidExperiment {
+    #[error("There must be at least one creature")]
+    NoCreatures,
+    #[error("The world must be at least {MIN_WORLD_DIM} wide and high")]
+    WorldTooSmall,
+    #[error("At least one species needs a share above 0")]
+    NoSpecies,
+    #[error("The time step must be above 0")]
+    TimeStep,
+    #[error("The brain template of {species} is invalid: {error}")]
+    Template {
+        species: String,
+        error: TemplateError,
+    },
+    #[error("The resource field's cell size must be above 0")]
+    FieldCellSize,
+    #[error("Children need at least one mutation")]
+    NoMutations,
+    /// Names the offending setting
+    #[error("{0} must be a number of 0 or above")]
+    Negative(String),
+}
+
+/// A whole experiment, loaded from and saved to RON files by the control panel and
+/// headless runs. Missing fields take their defaults
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Experiment {
+    pub num_creatures: usize,
+    pub dims: (f32, f32),
+    /// Seeds everything random in a run, the same seed and settings replay the same run
+    /// until creatures are edited, killed or cloned by hand. A random seed is picked and
+    /// recorded when None
+    pub seed: Option<u64>,
+    /// Brain inputs and outputs of species without a template of their own
+    pub template: Template,
+    pub sim: SimConfig,
+}
+
+impl Default for Experiment {
+    fn default() -> Self {
+        Self {
+            num_creatures: 500,
+            dims: (1280.0, 720.0),
+            seed: None,
+            template: Default::default(),
+            sim: Default::default(),
+        }
+    }
+}
+
+impl Experiment {
+    pub fn load(path: impl AsRef<Path>) -> Result<Experiment, ConfigError> {
+        Ok(ron::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), ConfigError> {
+        fs::write(path, ron::ser::to_string_pretty(self, Default::default())?)?;
+        Ok(())
+    }
+
+    /// Everything wrong with the experiment, empty if a simulation can be created from it
+    pub fn errors(&self) -> Vec<InvalidExperiment> {
+        let mut errors = Vec::new();
+        if self.num_creatures == 0 {
+            errors.push(InvalidExperiment::NoCreatures);
+        }
+        let (width, height) = self.dims;
+        if width.is_nan() || height.is_nan() || width.min(height) < MIN_WORLD_DIM {
+            errors.push(InvalidExperiment::WorldTooSmall);
+        }
+        if !self.sim.species.iter().any(|s| s.share > 0.0) {
+            errors.push(InvalidExperiment::NoSpecies);
+        }
+        if self.sim.dt.is_nan() || self.sim.dt <= 0.0 {
+            errors.push(InvalidExperiment::TimeStep);
+        }
+        for sp in self.sim.species.iter() {
+            if let Err(error) = sp.template.as_ref().unwrap_or(&self.template).check() {
+                errors.push(InvalidExperiment::Template {
+                    species: sp.name.clone(),
+                    error,
+                });
+            }
+        }
+        let field = &self.sim.food.field;
+        // the field isn't built while disabled
+        if field.enabled && (field.cell_size.is_nan() || field.cell_size <= 0.0) {
+            errors.push(InvalidExperiment::FieldCellSize);
+        }
+        if self.sim.evolution.mutations == 0 {
+            errors.push(InvalidExperiment::NoMutations);
+        }
+        errors.extend(
+            self.amounts()
+                .into_iter()
+                .filter(|(_, v)| v.is_nan() || *v < 0.0)
+                .map(|(name, _)| InvalidExperiment::Negative(name)),
+        );
+        errors
+    }
+
+    /// Shares, rates and other amounts that make no sense below 0, with the names errors show
+    fn amounts(&self) -> Vec<(String, f32)> {
+        let (food, field, evolution) = (&self.sim.food, &self.sim.food.field, &self.sim.evolution);
+        let mut amounts = vec![
+            ("initial_per_creature", food.initial_per_creature),
+            ("spawn_rate", food.spawn_rate),
+            ("max_density", food.max_density),
+            ("season_amplitude", food.season_amplitude),
+            ("patch_radius", food.patch_radius),
+            ("field max", field.max),
+            ("field regrowth", field.regrowth),
+            ("field bite", field.bite),
+            ("sensor range", self.sim.sensors.range),
+            ("mate_hunger_cost", evolution.mate_hunger_cost),
+            ("starvation_damage", evolution.starvation_damage),
+        ]
+        .into_iter()
+        .map(|(name, v)| (name.to_owned(), v))
+        .collect::<Vec<_>>();
+        for t in food.types.iter() {
+            amounts.push((format!("{} weight", t.name), t.weight));
+        }
+        for sp in self.sim.species.iter() {
+            for (name, v) in [
+                ("share", sp.share),
+                ("max_speed", sp.max_speed),
+                ("turn_rate", sp.turn_rate),
+                ("metabolism", sp.metabolism),
+                ("attack_damage", sp.attack_damage),
+                ("meat_nutrition", sp.meat_nutrition),
+            ] {
+                amounts.push((format!("{} {name}", sp.name), v));
+            }
+        }
+        amounts
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EvolutionConfig {
+    /// Ticks a creature has to wait between matings
+    pub mate_cooldown: usize,
+    /// Hunger both parents gain from mating
+    pub mate_hunger_cost: f32,
+    /// Hunger above which a creature starts losing health
+    pub max_hunger: f32,
+    /// Health lost per tick while starving
+    pub starvation_damage: f32,
+    /// Mutations applied to every child's brain, at least one
+    pub mutations: usize,
+}
+
+impl Default for EvolutionConfig {
+    fn default() -> Self {
+        Self {
+            mate_cooldown: 200,
+            mate_hunger_cost: 1.0,
+            max_hunger: 10.0,
+            starvation_damage: 0.1,
+            mutations: 1,
+        }
+    }
+}
+
+/// Everything a new simulation is created from besides the brains' inputs and outputs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SimConfig {
+    /// Simulated time per tick, scales movement, turning, hunger and damage
+    pub dt: f32,
+    pub boundary: Boundary,
+    pub sensors: SensorConfig,
+    pub food: FoodConfig,
+    pub world: WorldMap,
+    pub species: Vec<Species>,
+    pub evolution: EvolutionConfig,
+    pub record: RecordConfig,
+}
+
+impl Default for SimConfig {
+    fn default() -> Self {
+        Self {
+            dt: 1.0,
+            boundary: Default::default(),
+            sensors: Default::default(),
+            food: Default::default(),
+            world: Default::default(),
+            species: vec![Species::herbivore(), Species::carnivore()],
+            evolution: Default::default(),
+            record: Default::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Experiment, InvalidExperiment};
+
+    #[test]
+    fn default_is_valid() {
+        assert!(Experiment::default().errors().is_empty());
+    }
+
+    #[test]
+    fn reports_every_error() {
+        let mut e = Experiment {
+            num_creatures: 0,
+            dims: (f32::NAN, 720.0),
+            ..Default::default()
+        };
+        e.sim.dt = 0.0;
+        e.sim.species[0].share = f32::NAN;
+        e.sim.species[1].share = 0.0;
+        e.sim.species[1].metabolism = -1.0;
+        e.sim.species[1].template = Some(Default::default());
+        e.sim.species[1]
+            .template
+            .as_mut()
+            .unwrap()
+            .output_nodes
+            .clear();
+        e.sim.food.spawn_rate = -0.5;
+        e.sim.food.field.enabled = true;
+        e.sim.food.field.cell_size = 0.0;
+        e.sim.evolution.mutations = 0;
+
+        let errors = e.errors();
+        let has = |f: fn(&InvalidExperiment) -> bool| errors.iter().filter(|e| f(e)).count();
+        assert_eq!(has(|e| matches!(e, InvalidExperiment::NoCreatures)), 1);
+        assert_eq!(has(|e| matches!(e, InvalidExperiment::WorldTooSmall)), 1);
+        assert_eq!(has(|e| matches!(e, InvalidExperiment::NoSpecies)), 1);
+        assert_eq!(has(|e| matches!(e, InvalidExperiment::TimeStep)), 1);
+        assert_eq!(has(|e| matches!(e, InvalidExperiment::FieldCellSize)), 1);
+        assert_eq!(has(|e| matches!(e, InvalidExperiment::NoMutations)), 1);
+        assert_eq!(
+            has(
+                |e| matches!(e, InvalidExperiment::Template { species, .. } if species == "carnivore")
+            ),
+            1
+        );
+        let mut negative = errors
+            .iter()
+            .filter_map(|e| match e {
+                InvalidExperiment::Negative(name) => Some(name.as_str()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        negative.sort();
+        assert_eq!(
+            negative,
+            ["carnivore metabolism", "herbivore share", "spawn_rate"]
+        );
+        assert_eq!(errors.len(), 10);
+    }
+
+    #[test]
+    fn disabled_field_is_not_checked() {
+        let mut e = Experiment::default();
+        e.sim.food.field.cell_size = 0.0;
+        assert!(e.errors().is_empty());
+    }
+
+    #[test]
+    fn ron_round_trip() {
+        let mut e = Experiment {
+            seed: Some(7),
+            num_creatures: 42,
+            ..Default::default()
+        };
+        e.sim.species[1].template = Some(Default::default());
+        let text = ron::ser::to_string_pretty(&e, Default::default()).unwrap();
+        let loaded: Experiment = ron::from_str(&text).unwrap();
+        assert_eq!(
+            text,
+            ron::ser::to_string_pretty(&loaded, Default::default()).unwrap()
+        );
+        assert_eq!(loaded.seed, Some(7));
+        assert_eq!(loaded.num_creatures, 42);
+        assert!(loaded.errors().is_empty());
+    }
+
+    #[test]
+    fn missing_fields_take_defaults() {
+        let e: Experiment = ron::from_str("(num_creatures: 10, sim: (dt: 0.5))").unwrap();
+        assert_eq!(e.num_creatures, 10);
+        assert_eq!(e.sim.dt, 0.5);
+        assert_eq!(e.dims, Experiment::default().dims);
+        assert_eq!(e.sim.species.len(), 2);
+    }
+}
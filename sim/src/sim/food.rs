@@ -0,0 +1,274 @@
+use std::f32::consts::TAU;
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use super::world::{TerrainKind, WorldMap};
+
+/// Attempts at finding a spot for food outside of obstacles
+const SPAWN_TRIES: usize = 10;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FoodType {
+    pub name: String,
+    /// How much hunger eating one item removes
+    pub nutrition: f32,
+    /// Relative chance of this type being spawned
+    pub weight: f32,
+    pub color: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FieldConfig {
+    pub enabled: bool,
+    pub cell_size: f32,
+    pub max: f32,
+    /// Amount every cell regrows per tick
+    pub regrowth: f32,
+    /// Amount taken from a cell by a creature eating there for a tick
+    pub bite: f32,
+    /// Hunger removed per unit taken
+    pub nutrition: f32,
+}
+
+impl Default for FieldConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            cell_size: 50.0,
+            max: 1.0,
+            regrowth: 0.001,
+            bite: 0.1,
+            nutrition: 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FoodConfig {
+    /// Food items spawned at the start for every creature
+    pub initial_per_creature: f32,
+    /// Food items spawned per tick on average
+    pub spawn_rate: f32,
+    /// Maximum number of food items per 100x100 area
+    pub max_density: f32,
+    /// Length of a full season cycle in ticks, 0 disables seasons
+    pub season_length: usize,
+    /// How much seasons change the spawn rate, 1 stops spawning in winter
+    pub season_amplitude: f32,
+    /// Number of patches food grows in, 0 spreads it over the whole world
+    pub patches: usize,
+    pub patch_radius: f32,
+    pub types: Vec<FoodType>,
+    pub field: FieldConfig,
+}
+
+impl Default for FoodConfig {
+    fn default() -> Self {
+        Self {
+            initial_per_creature: 0.25,
+            spawn_rate: 0.5,
+            max_density: 2.0,
+            season_length: 0,
+            season_amplitude: 0.5,
+            patches: 0,
+            patch_radius: 100.0,
+            types: vec![FoodType {
+                name: "plant".to_owned(),
+                nutrition: 1.0,
+                weight: 1.0,
+                color: "54ff71".to_owned(),
+            }],
+            field: FieldConfig::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default)]
+pub struct Food {
+    /// Unique within a simulation, lets the drawn shapes follow the same food
+    pub id: usize,
+    pub position: (f32, f32),
+    /// Index into `FoodConfig::types`
+    pub kind: usize,
+}
+
+/// Places new food and keeps track of how much is owed between ticks
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct FoodSpawner {
+    patches: Vec<(f32, f32)>,
+    owed: f32,
+    /// Owed food of every terrain zone, fertile or not
+    zone_owed: Vec<f32>,
+    last_id: usize,
+}
+
+impl FoodSpawner {
+    pub fn new(config: &FoodConfig, world_dim: (f32, f32), rng: &mut impl Rng) -> FoodSpawner {
+        FoodSpawner {
+            patches: (0..config.patches)
+                .map(|_| {
+                    (
+                        rng.gen_range(0.0..=world_dim.0),
+                        rng.gen_range(0.0..=world_dim.1),
+                    )
+                })
+                .collect(),
+            owed: 0.0,
+            zone_owed: Vec::new(),
+            last_id: 0,
+        }
+    }
+
+    pub fn spawn(
+        &mut self,
+        config: &FoodConfig,
+        world: &WorldMap,
+        world_dim: (f32, f32),
+        rng: &mut impl Rng,
+    ) -> Option<Food> {
+        let position = (0..SPAWN_TRIES)
+            .map(|_| self.position(config, world_dim, rng))
+            .find(|p| !world.blocked(*p))?;
+        self.last_id += 1;
+        Some(Food {
+            id: self.last_id,
+            position,
+            kind: Self::kind(config, rng),
+        })
+    }
+
+    fn position(
+        &self,
+        config: &FoodConfig,
+        world_dim: (f32, f32),
+        rng: &mut impl Rng,
+    ) -> (f32, f32) {
+        if self.patches.is_empty() {
+            (
+                rng.gen_range(0.0..=world_dim.0),
+                rng.gen_range(0.0..=world_dim.1),
+            )
+        } else {
+            let center = self.patches[rng.gen_range(0..self.patches.len())];
+            let angle = rng.gen_range(0.0..TAU);
+            // sqrt keeps the patch evenly filled instead of crowding the center
+            let dist = config.patch_radius * rng.gen_range(0.0f32..=1.0).sqrt();
+            (
+                (center.0 + angle.cos() * dist).clamp(0.0, world_dim.0),
+                (center.1 + angle.sin() * dist).clamp(0.0, world_dim.1),
+            )
+        }
+    }
+
+    fn kind(config: &FoodConfig, rng: &mut impl Rng) -> usize {
+        let total = config.types.iter().map(|t| t.weight.max(0.0)).sum::<f32>();
+        let mut pick = rng.gen_range(0.0..=1.0) * total;
+        config
+            .types
+            .iter()
+            .position(|t| {
+                pick -= t.weight.max(0.0);
+                pick <= 0.0
+            })
+            .unwrap_or(0)
+    }
+
+    /// New food for this tick, respecting the seasonal rate and the density cap
+    pub fn tick(
+        &mut self,
+        config: &FoodConfig,
+        world: &WorldMap,
+        world_dim: (f32, f32),
+        ticks: usize,
+        current: usize,
+        rng: &mut impl Rng,
+    ) -> Vec<Food> {
+        if config.types.is_empty() {
+            return Vec::new();
+        }
+
+        let season = if config.season_length > 0 {
+            let phase = (ticks % config.season_length) as f32 / config.season_length as f32;
+            (1.0 + config.season_amplitude * (phase * TAU).sin()).max(0.0)
+        } else {
+            1.0
+        };
+        self.owed += config.spawn_rate * season;
+
+        let max = (config.max_density * world_dim.0 * world_dim.1 / 10_000.0) as usize;
+        let count = (self.owed.floor() as usize).min(max.saturating_sub(current));
+        self.owed -= self.owed.floor();
+        let mut spawned = (0..count)
+            .filter_map(|_| self.spawn(config, world, world_dim, rng))
+            .collect::<Vec<_>>();
+
+        self.zone_owed.resize(world.terrain.len(), 0.0);
+        for (t, owed) in world.terrain.iter().zip(self.zone_owed.iter_mut()) {
+            if let TerrainKind::Fertile { spawn_rate } = t.kind {
+                *owed += spawn_rate * season;
+            }
+            while *owed >= 1.0 && current + spawned.len() < max {
+                *owed -= 1.0;
+                if let Some(position) = t.shape.sample(rng).filter(|p| !world.blocked(*p)) {
+                    self.last_id += 1;
+                    spawned.push(Food {
+                        id: self.last_id,
+                        position,
+                        kind: Self::kind(config, rng),
+                    });
+                }
+            }
+            *owed = owed.min(1.0);
+        }
+        spawned
+    }
+}
+
+/// Continuous resource spread over the world in square cells
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ResourceField {
+    cell_size: f32,
+    cols: usize,
+    rows: usize,
+    values: Vec<f32>,
+}
+
+impl ResourceField {
+    pub fn new(config: &FieldConfig, world_dim: (f32, f32)) -> ResourceField {
+        let cols = (world_dim.0 / config.cell_size).ceil().max(1.0) as usize;
+        let rows = (world_dim.1 / config.cell_size).ceil().max(1.0) as usize;
+        ResourceField {
+            cell_size: config.cell_size,
+            cols,
+            rows,
+            values: vec![config.max; cols * rows],
+        }
+    }
+
+    fn idx(&self, p: (f32, f32)) -> usize {
+        let x = ((p.0 / self.cell_size).max(0.0) as usize).min(self.cols - 1);
+        let y = ((p.1 / self.cell_size).max(0.0) as usize).min(self.rows - 1);
+        y * self.cols + x
+    }
+
+    pub fn sample(&self, p: (f32, f32)) -> f32 {
+        self.values[self.idx(p)]
+    }
+
+    /// Removes up to `amount` from the cell at `p`, returning what was taken
+    pub fn take(&mut self, p: (f32, f32), amount: f32) -> f32 {
+        let idx = self.idx(p);
+        let taken = self.values[idx].min(amount);
+        self.values[idx] -= taken;
+        taken
+    }
+
+    pub fn regrow(&mut self, config: &FieldConfig) {
+        for v in self.values.iter_mut() {
+            *v = (*v + config.regrowth).min(config.max);
+        }
+    }
+}
@@ -0,0 +1,126 @@
+/// Uniform grid over the world holding indices into a list of positions, rebuilt every tick
+#[derive(Debug, Clone, Default)]
+pub struct Grid {
+    cell_size: f32,
+    world_dim: (f32, f32),
+    cols: usize,
+    rows: usize,
+    /// Queries crossing an edge continue on the opposite side
+    wrap: bool,
+    cells: Vec<Vec<usize>>,
+}
+
+impl Grid {
+    pub fn new<'a>(
+        world_dim: (f32, f32),
+        cell_size: f32,
+        wrap: bool,
+        positions: impl Iterator<Item = &'a (f32, f32)>,
+    ) -> Grid {
+        let cols = (world_dim.0 / cell_size).ceil().max(1.0) as usize;
+        let rows = (world_dim.1 / cell_size).ceil().max(1.0) as usize;
+        let mut grid = Grid {
+            cell_size,
+            world_dim,
+            cols,
+            rows,
+            wrap,
+            cells: vec![Vec::new(); cols * rows],
+        };
+
+        for (idx, p) in positions.enumerate() {
+            let x = grid.index(p.0, cols);
+            let y = grid.index(p.1, rows);
+            grid.cells[y * cols + x].push(idx);
+        }
+        grid
+    }
+
+    fn index(&self, v: f32, n: usize) -> usize {
+        let i = (v / self.cell_size).floor() as isize;
+        if self.wrap {
+            i.rem_euclid(n as isize) as usize
+        } else {
+            i.clamp(0, n as isize - 1) as usize
+        }
+    }
+
+    /// Cells along one axis covering `center - radius..=center + radius`
+    fn span(&self, center: f32, radius: f32, n: usize, world: f32) -> Vec<usize> {
+        if !self.wrap {
+            return (self.index(center - radius, n)..=self.index(center + radius, n)).collect();
+        }
+        if 2.0 * radius >= world {
+            return (0..n).collect();
+        }
+        // wrap in world coordinates, the last cell may be cut short by the seam
+        let lo = (center - radius).rem_euclid(world);
+        let hi = (center + radius).rem_euclid(world);
+        let (min, max) = (self.index(lo, n), self.index(hi, n));
+        if lo <= hi {
+            (min..=max).collect()
+        } else if min > max {
+            (min..n).chain(0..=max).collect()
+        } else {
+            (0..n).collect()
+        }
+    }
+
+    /// Indices in every cell overlapping the square around `center`, may contain some further away
+    pub fn query(&self, center: (f32, f32), radius: f32) -> impl Iterator<Item = usize> + '_ {
+        let cols = self.span(center.0, radius, self.cols, self.world_dim.0);
+        let rows = self.span(center.1, radius, self.rows, self.world_dim.1);
+        rows.into_iter().flat_map(move |y| {
+            cols.clone()
+                .into_iter()
+                .flat_map(move |x| self.cells[y * self.cols + x].iter().cloned())
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Grid;
+
+    fn found(grid: &Grid, center: (f32, f32), radius: f32) -> Vec<usize> {
+        let mut found: Vec<usize> = grid.query(center, radius).collect();
+        found.sort();
+        found
+    }
+
+    #[test]
+    fn query_nearby() {
+        let positions = [(5.0, 5.0), (15.0, 5.0), (95.0, 95.0)];
+        let grid = Grid::new((100.0, 100.0), 10.0, false, positions.iter());
+        assert_eq!(found(&grid, (8.0, 5.0), 4.0), vec![0, 1]);
+        assert_eq!(found(&grid, (2.0, 2.0), 1.0), vec![0]);
+        assert_eq!(found(&grid, (99.0, 99.0), 1.0), vec![2]);
+        assert_eq!(found(&grid, (50.0, 50.0), 100.0), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn clamp_does_not_cross_edges() {
+        let positions = [(1.0, 50.0)];
+        let grid = Grid::new((100.0, 100.0), 10.0, false, positions.iter());
+        assert!(found(&grid, (98.0, 50.0), 5.0).is_empty());
+    }
+
+    #[test]
+    fn wrap_crosses_edges() {
+        let positions = [(1.0, 50.0), (50.0, 1.0)];
+        let grid = Grid::new((100.0, 100.0), 10.0, true, positions.iter());
+        assert_eq!(found(&grid, (98.0, 50.0), 5.0), vec![0]);
+        assert_eq!(found(&grid, (50.0, 98.0), 5.0), vec![1]);
+        assert_eq!(found(&grid, (1.0, 50.0), 0.5), vec![0]);
+    }
+
+    #[test]
+    fn wrap_with_partial_last_cell() {
+        // 110 wide with 20 wide cells, the last cell only covers 100..110
+        let positions = [(1.0, 5.0), (105.0, 5.0)];
+        let grid = Grid::new((110.0, 20.0), 20.0, true, positions.iter());
+        assert_eq!(found(&grid, (108.0, 5.0), 5.0), vec![0, 1]);
+        assert_eq!(found(&grid, (3.0, 5.0), 5.0), vec![0, 1]);
+        assert_eq!(found(&grid, (60.0, 5.0), 5.0), Vec::<usize>::new());
+    }
+}
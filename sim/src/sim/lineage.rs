@@ -0,0 +1,184 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct LineageRecord {
+    pub parents: Option<(usize, usize)>,
+    pub generation: usize,
+    pub birth_tick: usize,
+    pub death_tick: Option<usize>,
+    pub children: Vec<usize>,
+}
+
+/// Every creature that ever lived in the current simulation, kept after death
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct Lineage {
+    records: HashMap<usize, LineageRecord>,
+}
+
+/// Ancestors and descendants of `root` up to a fixed depth
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct FamilyTree {
+    pub root: usize,
+    pub records: HashMap<usize, LineageRecord>,
+}
+
+impl Lineage {
+    pub fn clear(&mut self) {
+        self.records.clear();
+    }
+
+    pub fn birth(
+        &mut self,
+        id: usize,
+        parents: Option<(usize, usize)>,
+        generation: usize,
+        birth_tick: usize,
+    ) {
+        if let Some((a, b)) = parents {
+            // clones have their original as both parents
+            let parents = if a == b { vec![a] } else { vec![a, b] };
+            for parent in parents {
+                if let Some(r) = self.records.get_mut(&parent) {
+                    r.children.push(id);
+                }
+            }
+        }
+
+        self.records.insert(
+            id,
+            LineageRecord {
+                parents,
+                generation,
+                birth_tick,
+                death_tick: None,
+                children: Vec::new(),
+            },
+        );
+    }
+
+    pub fn death(&mut self, id: usize, tick: usize) {
+        if let Some(r) = self.records.get_mut(&id) {
+            r.death_tick = Some(tick);
+        }
+    }
+
+    pub fn family_tree(&self, root: usize, depth: usize) -> Option<FamilyTree> {
+        self.records.get(&root)?;
+
+        let mut records = HashMap::new();
+        let mut queue = VecDeque::from([(root, 0)]);
+        while let Some((id, d)) = queue.pop_front() {
+            let r = match self.records.get(&id) {
+                Some(r) => r,
+                None => continue,
+            };
+            if records.insert(id, r.clone()).is_some() || d == depth {
+                continue;
+            }
+
+            if let Some((a, b)) = r.parents {
+                queue.push_back((a, d + 1));
+                queue.push_back((b, d + 1));
+            }
+        }
+
+        let mut queue = VecDeque::from([(root, 0)]);
+        while let Some((id, d)) = queue.pop_front() {
+            let r = match self.records.get(&id) {
+                Some(r) => r,
+                None => continue,
+            };
+            records.insert(id, r.clone());
+            if d == depth {
+                continue;
+            }
+
+            for c in r.children.iter() {
+                queue.push_back((*c, d + 1));
+            }
+        }
+
+        Some(FamilyTree { root, records })
+    }
+
+    /// All descendants of `id` that have not died yet
+    pub fn living_descendants(&self, id: usize) -> HashSet<usize> {
+        let mut visited = HashSet::new();
+        let mut stack = match self.records.get(&id) {
+            Some(r) => r.children.clone(),
+            None => return visited,
+        };
+
+        while let Some(c) = stack.pop() {
+            if !visited.insert(c) {
+                continue;
+            }
+            if let Some(r) = self.records.get(&c) {
+                stack.extend(r.children.iter().cloned());
+            }
+        }
+
+        visited.retain(|c| self.records.get(c).is_some_and(|r| r.death_tick.is_none()));
+        visited
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use super::Lineage;
+
+    /// 1 and 2 have 3, 3 and 4 have 5, 5 is cloned into 6
+    fn lineage() -> Lineage {
+        let mut l = Lineage::default();
+        for id in [1, 2, 4] {
+            l.birth(id, None, 0, 0);
+        }
+        l.birth(3, Some((1, 2)), 1, 10);
+        l.birth(5, Some((3, 4)), 2, 20);
+        l.birth(6, Some((5, 5)), 3, 30);
+        l
+    }
+
+    #[test]
+    fn records_children() {
+        let l = lineage();
+        let tree = l.family_tree(5, 2).unwrap();
+        assert_eq!(tree.records[&1].children, vec![3]);
+        assert_eq!(tree.records[&3].children, vec![5]);
+        assert_eq!(tree.records[&5].children, vec![6]);
+        assert_eq!(tree.records[&5].generation, 2);
+    }
+
+    #[test]
+    fn family_tree_depth() {
+        let l = lineage();
+        assert!(l.family_tree(7, 3).is_none());
+
+        let ids = |depth| {
+            let tree = l.family_tree(5, depth).unwrap();
+            assert_eq!(tree.root, 5);
+            tree.records.keys().cloned().collect::<HashSet<_>>()
+        };
+        assert_eq!(ids(0), HashSet::from([5]));
+        assert_eq!(ids(1), HashSet::from([3, 4, 5, 6]));
+        assert_eq!(ids(2), HashSet::from([1, 2, 3, 4, 5, 6]));
+    }
+
+    #[test]
+    fn living_descendants() {
+        let mut l = lineage();
+        assert_eq!(l.living_descendants(1), HashSet::from([3, 5, 6]));
+        l.death(5, 40);
+        assert_eq!(l.living_descendants(1), HashSet::from([3, 6]));
+        assert_eq!(
+            l.family_tree(5, 0).unwrap().records[&5].death_tick,
+            Some(40)
+        );
+        assert!(l.living_descendants(6).is_empty());
+        assert!(l.living_descendants(7).is_empty());
+    }
+}
@@ -5,9 +5,56 @@ use crate::{InspectWindowState, TabState};
 
 use self::{resources::*, systems::*};
 
+mod config;
+mod food;
+mod grid;
+mod lineage;
+mod recorder;
 mod resources;
+mod sensors;
 mod sim;
+mod snapshot;
+mod species;
+mod stats;
 mod systems;
+mod world;
+
+/// Runs `<experiment.ron> [ticks]` without a window, printing the final stats
+pub fn run_headless(args: &[String]) {
+    let Some(path) = args.first() else {
+        eprintln!("Usage: --headless <experiment.ron> [ticks]");
+        std::process::exit(2);
+    };
+    let ticks = match args.get(1).map(|t| t.parse::<usize>()) {
+        None => 1000,
+        Some(Ok(t)) => t,
+        Some(Err(e)) => {
+            eprintln!("Invalid tick count: {e}");
+            std::process::exit(2);
+        }
+    };
+    let experiment = match config::Experiment::load(path) {
+        Ok(e) => e,
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+    };
+    let errors = experiment.errors();
+    if !errors.is_empty() {
+        for e in errors {
+            eprintln!("{e}");
+        }
+        std::process::exit(1);
+    }
+
+    let (stats, recording) = sim::Runner::headless(experiment, ticks);
+    println!("{}", stats::TickStats::CSV_HEADER);
+    println!("{}", stats.csv_row());
+    if let Some(status) = recording {
+        eprintln!("{status}");
+    }
+}
 
 pub struct SimulationPlugin;
 
@@ -15,6 +62,9 @@ impl Plugin for SimulationPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<ControlPanel>()
             .init_resource::<Simulation>()
+            .init_resource::<Selection>()
+            .init_resource::<WorldEntities>()
+            .init_resource::<Stats>()
             .init_state::<SimulationState>()
             .add_plugins(Shape2dPlugin::default())
             .add_systems(Startup, init_runner)
@@ -29,13 +79,54 @@ impl Plugin for SimulationPlugin {
             )
             .add_systems(
                 Update,
-                run_simulation.run_if(in_state(SimulationState::Running)),
+                refresh_lineage
+                    .after(collect_stats)
+                    .run_if(in_state(SimulationState::Running)),
             )
             .add_systems(
                 Update,
-                inspect_creature
-                    .run_if(in_state(InspectWindowState::Display))
+                // also runs while paused to show single steps
+                run_simulation.run_if(not(in_state(SimulationState::None))),
+            )
+            .add_systems(
+                Update,
+                inspect_creature.run_if(in_state(TabState::Simulation)),
+            )
+            .add_systems(
+                Update,
+                world_camera
+                    .after(run_simulation)
                     .run_if(in_state(TabState::Simulation)),
+            )
+            .add_systems(
+                Update,
+                apply_edited_net.run_if(in_state(TabState::Simulation)),
+            )
+            .add_systems(
+                Update,
+                (
+                    collect_stats,
+                    stats_window.run_if(|panel: Res<ControlPanel>| panel.show_stats),
+                )
+                    .chain()
+                    .run_if(in_state(TabState::Simulation)),
+            )
+            .add_systems(
+                Update,
+                (
+                    refresh_readout
+                        .after(run_simulation)
+                        .run_if(not(in_state(SimulationState::None))),
+                    selection_panel.before(control_panel),
+                )
+                    .run_if(in_state(TabState::Simulation)),
+            )
+            .add_systems(
+                Update,
+                follow_inspected
+                    .after(run_simulation)
+                    .run_if(in_state(InspectWindowState::Display))
+                    .run_if(not(in_state(SimulationState::None))),
             );
     }
 }
@@ -0,0 +1,186 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::{config::Experiment, sim::BasicCreature, stats::TickStats};
+
+/// Ticks between flushes, so little is lost when the app is closed mid run
+const FLUSH_INTERVAL: usize = 100;
+
+/// What the runner writes to disk while a simulation runs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RecordConfig {
+    pub enabled: bool,
+    /// Every run gets its own directory in here
+    pub dir: String,
+    /// Ticks between rows of population aggregates
+    pub stats_interval: usize,
+    /// Ticks between rows for every living creature, 0 turns them off
+    pub creatures_interval: usize,
+    /// Births, deaths, meals and matings of every creature
+    pub events: bool,
+}
+
+impl Default for RecordConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            dir: "runs".to_owned(),
+            stats_interval: 1,
+            creatures_interval: 0,
+            events: true,
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum RecordError {
+    #[error("Could not write run files: {0}")]
+    Io(#[from] io::Error),
+    #[error("Could not save the experiment: {0}")]
+    Config(#[from] super::config::ConfigError),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum EventKind {
+    Birth,
+    Death,
+    /// Ate a food item, grazing the resource field isn't logged
+    Eat,
+    /// `other` is the partner, the position is where the child was born
+    Mate,
+}
+
+impl EventKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            EventKind::Birth => "birth",
+            EventKind::Death => "death",
+            EventKind::Eat => "eat",
+            EventKind::Mate => "mate",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct CreatureEvent {
+    pub kind: EventKind,
+    pub id: usize,
+    pub other: Option<usize>,
+    pub position: (f32, f32),
+}
+
+/// CSV files of a single run: stats.csv, events.csv and creatures.csv next to the
+/// experiment the simulation was created from
+pub struct Recorder {
+    pub dir: PathBuf,
+    config: RecordConfig,
+    stats: BufWriter<File>,
+    events: Option<BufWriter<File>>,
+    creatures: Option<BufWriter<File>>,
+}
+
+fn csv(path: impl AsRef<Path>, header: &str) -> io::Result<BufWriter<File>> {
+    let mut file = BufWriter::new(File::create(path)?);
+    writeln!(file, "{header}")?;
+    Ok(file)
+}
+
+impl Recorder {
+    pub fn create(config: &RecordConfig, experiment: &Experiment) -> Result<Recorder, RecordError> {
+        let started = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        let dir = Path::new(&config.dir).join(format!("run-{started}"));
+        fs::create_dir_all(&dir)?;
+        experiment.save(dir.join("experiment.ron"))?;
+
+        Ok(Recorder {
+            stats: csv(dir.join("stats.csv"), TickStats::CSV_HEADER)?,
+            events: if config.events {
+                Some(csv(dir.join("events.csv"), "tick,event,id,other,x,y")?)
+            } else {
+                None
+            },
+            creatures: match config.creatures_interval {
+                0 => None,
+                _ => Some(csv(
+                    dir.join("creatures.csv"),
+                    "tick,id,species,generation,x,y,heading,hunger,health",
+                )?),
+            },
+            config: config.clone(),
+            dir,
+        })
+    }
+
+    pub fn wants_creatures(&self, tick: usize) -> bool {
+        self.creatures.is_some() && tick.is_multiple_of(self.config.creatures_interval)
+    }
+
+    pub fn record(
+        &mut self,
+        stats: &TickStats,
+        events: &[CreatureEvent],
+        creatures: &[BasicCreature],
+    ) -> io::Result<()> {
+        let tick = stats.tick;
+        if tick.is_multiple_of(self.config.stats_interval.max(1)) {
+            writeln!(self.stats, "{}", stats.csv_row())?;
+        }
+
+        if let Some(file) = self.events.as_mut() {
+            for e in events {
+                let other = e.other.map_or(String::new(), |o| o.to_string());
+                writeln!(
+                    file,
+                    "{tick},{},{},{other},{},{}",
+                    e.kind.as_str(),
+                    e.id,
+                    e.position.0,
+                    e.position.1
+                )?;
+            }
+        }
+
+        if let Some(file) = self.creatures.as_mut() {
+            for c in creatures {
+                writeln!(
+                    file,
+                    "{tick},{},{},{},{},{},{},{},{}",
+                    c.id,
+                    c.species,
+                    c.generation,
+                    c.position.0,
+                    c.position.1,
+                    c.heading,
+                    c.hunger,
+                    c.health
+                )?;
+            }
+        }
+
+        if tick.is_multiple_of(FLUSH_INTERVAL) {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.stats.flush()?;
+        for file in [self.events.as_mut(), self.creatures.as_mut()]
+            .into_iter()
+            .flatten()
+        {
+            file.flush()?;
+        }
+        Ok(())
+    }
+}
@@ -1,16 +1,77 @@
-use std::thread::JoinHandle;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    thread::JoinHandle,
+};
 
-use bevy::ecs::{schedule::States, system::Resource};
+use bevy::ecs::{entity::Entity, schedule::States, system::Resource};
 use flume::{Receiver, Sender};
 use serde::{Deserialize, Serialize};
 
-use super::sim::{BasicCreature, RunnerReq, RunnerRes};
+use super::{
+    config::Experiment,
+    food::{Food, FoodType},
+    lineage::FamilyTree,
+    sim::{BasicCreature, Readout, Reply, RequestId, RunnerReq, RunnerRes},
+    snapshot::SnapshotBuffer,
+    species::Species,
+    stats::TickStats,
+    world::{Boundary, WorldMap},
+};
 
 #[derive(Resource)]
 pub struct RunnerResource {
     pub tx: Sender<RunnerReq>,
-    pub rx: Receiver<RunnerRes>,
+    pub rx: Receiver<Reply>,
+    /// Aggregates of every tick, in order
+    pub stats: Receiver<TickStats>,
     pub thread: JoinHandle<()>,
+    pub snapshot: Arc<SnapshotBuffer>,
+    next_id: AtomicU64,
+    /// Held from sending a request until its reply arrived, so systems running in
+    /// parallel can't take each other's replies
+    in_flight: Mutex<()>,
+}
+
+impl RunnerResource {
+    pub fn new(
+        tx: Sender<RunnerReq>,
+        rx: Receiver<Reply>,
+        stats: Receiver<TickStats>,
+        thread: JoinHandle<()>,
+        snapshot: Arc<SnapshotBuffer>,
+    ) -> Self {
+        Self {
+            tx,
+            rx,
+            stats,
+            thread,
+            snapshot,
+            next_id: AtomicU64::new(0),
+            in_flight: Mutex::new(()),
+        }
+    }
+
+    /// Sends the request under a fresh id and waits for its reply, one request at a time.
+    /// Replies to earlier requests nobody waited for are dropped
+    pub fn request(&self, req: impl FnOnce(RequestId) -> RunnerReq) -> RunnerRes {
+        // a panic while waiting leaves nothing half done, so a poisoned lock is fine to reuse
+        let _in_flight = self
+            .in_flight
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.tx.send(req(id)).expect("Could not send request");
+        loop {
+            let reply = self.rx.recv().expect("Could not receive reply");
+            if reply.id == id {
+                return reply.res;
+            }
+        }
+    }
 }
 
 #[derive(Resource, Debug, Clone, Serialize, Deserialize, Default)]
@@ -18,25 +79,81 @@ pub struct Simulation {
     pub world_dim: (f32, f32),
     pub window_dims: (f32, f32),
     pub creatures: Vec<BasicCreature>,
-    pub food: Vec<(f32, f32)>,
+    pub food: Vec<Food>,
+    /// Food types of the running simulation, used for drawing
+    pub food_types: Vec<FoodType>,
+    /// Species of the running simulation, used for drawing
+    pub species: Vec<Species>,
+    pub boundary: Boundary,
+    pub world: WorldMap,
     pub ticks: usize,
+    /// Ticks per second the runner actually reached
+    pub tps: f32,
+    pub recording: Option<String>,
+}
+
+/// Aggregates of every tick of the current simulation
+#[derive(Resource, Debug, Default)]
+pub struct Stats {
+    pub history: Vec<TickStats>,
+}
+
+/// Shapes kept alive between frames, creatures and food by id
+#[derive(Resource, Debug, Default)]
+pub struct WorldEntities {
+    /// Also keyed by the seam copy, see `Boundary::seam_copies`
+    pub creatures: HashMap<(usize, (i8, i8)), Entity>,
+    pub food: HashMap<usize, Entity>,
+}
+
+/// Creature clicked in the world and the lineage shown for it
+#[derive(Resource, Debug, Default)]
+pub struct Selection {
+    pub creature: Option<usize>,
+    pub family_tree: Option<FamilyTree>,
+    /// Ancestor whose living descendants are highlighted
+    pub highlight_root: Option<usize>,
+    pub highlighted: HashSet<usize>,
+    /// Live state of `creature`, None once it died
+    pub readout: Option<Readout>,
+    /// Creatures pinned in the selection panel to come back to
+    pub favourites: Vec<usize>,
 }
 
 #[derive(Resource, Debug)]
 pub struct ControlPanel {
-    pub initial_num_creatures: String,
-    pub width: String,
-    pub height: String,
+    pub experiment: Experiment,
+    pub experiment_file: String,
+    pub experiment_status: Option<String>,
+    pub world_file: String,
+    pub world_error: Option<String>,
     pub can_create_sim: bool,
+    pub target_tps: f32,
+    pub unlimited_tps: bool,
+    /// Keeps the camera centered on the selected creature
+    pub follow_selected: bool,
+    pub reset_view: bool,
+    pub show_stats: bool,
+    pub stats_file: String,
+    pub stats_status: Option<String>,
 }
 
 impl Default for ControlPanel {
     fn default() -> Self {
         Self {
-            initial_num_creatures: "500".to_owned(),
-            width: "0".to_owned(),
-            height: "0".to_owned(),
+            experiment: Experiment::default(),
+            experiment_file: "sim/experiments/default.ron".to_owned(),
+            experiment_status: None,
+            world_file: "sim/worlds/example.ron".to_owned(),
+            world_error: None,
             can_create_sim: true,
+            target_tps: 60.0,
+            unlimited_tps: false,
+            follow_selected: false,
+            reset_view: false,
+            show_stats: false,
+            stats_file: "stats.csv".to_owned(),
+            stats_status: None,
         }
     }
 }
@@ -0,0 +1,162 @@
+use std::f32::consts::PI;
+
+use bevy::math::Vec2;
+use engine::nn::Node;
+use serde::{Deserialize, Serialize};
+
+use crate::inputs::{RayDistance, RayHit};
+
+use super::{sim::CREATURE_DIM_HALF, world::WorldMap};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SensorConfig {
+    pub rays: usize,
+    pub range: f32,
+    /// Angle covered by all rays together, in radians
+    pub fov: f32,
+}
+
+impl Default for SensorConfig {
+    fn default() -> Self {
+        Self {
+            rays: 5,
+            range: 100.0,
+            fov: PI / 2.0,
+        }
+    }
+}
+
+impl SensorConfig {
+    /// A distance and a hit input for every ray, ids start at `first_id`
+    pub fn input_nodes(&self, first_id: usize) -> Vec<Node> {
+        (0..self.rays)
+            .flat_map(|i| {
+                [
+                    Node::Input(RayDistance::new(1.0, first_id + i * 2)),
+                    Node::Input(RayHit::new(
+                        HitKind::Nothing.as_input(),
+                        first_id + i * 2 + 1,
+                    )),
+                ]
+            })
+            .collect()
+    }
+
+    /// Angle of ray `i` relative to the creature's heading, spread evenly over the field of view
+    pub fn ray_angle(&self, i: usize) -> f32 {
+        if self.rays <= 1 {
+            return 0.0;
+        }
+        -self.fov / 2.0 + self.fov * i as f32 / (self.rays - 1) as f32
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HitKind {
+    Nothing,
+    Food,
+    Creature,
+    Wall,
+    Obstacle,
+    /// Creature of another species
+    Stranger,
+}
+
+impl HitKind {
+    pub fn as_input(self) -> f32 {
+        match self {
+            HitKind::Nothing => 0.0,
+            HitKind::Food => 1.0,
+            HitKind::Creature => 2.0,
+            HitKind::Wall => 3.0,
+            HitKind::Obstacle => 4.0,
+            HitKind::Stranger => 5.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Hit {
+    /// Distance scaled by the ray range, 1 if nothing was hit
+    pub distance: f32,
+    pub kind: HitKind,
+}
+
+/// Distance along `dir` to a circle, `dir` must be normalized
+pub fn ray_circle(origin: Vec2, dir: Vec2, center: Vec2, radius: f32) -> Option<f32> {
+    let to_center = center - origin;
+    let along = to_center.dot(dir);
+    let dist_sq = to_center.length_squared() - along * along;
+    if dist_sq > radius * radius {
+        return None;
+    }
+
+    let t = along - (radius * radius - dist_sq).sqrt();
+    if t >= 0.0 {
+        Some(t)
+    } else if along >= 0.0 {
+        // origin is inside the circle
+        Some(0.0)
+    } else {
+        None
+    }
+}
+
+fn ray_wall(origin: Vec2, dir: Vec2, world_dim: (f32, f32)) -> f32 {
+    let tx = if dir.x > 0.0 {
+        (world_dim.0 - origin.x) / dir.x
+    } else if dir.x < 0.0 {
+        -origin.x / dir.x
+    } else {
+        f32::INFINITY
+    };
+    let ty = if dir.y > 0.0 {
+        (world_dim.1 - origin.y) / dir.y
+    } else if dir.y < 0.0 {
+        -origin.y / dir.y
+    } else {
+        f32::INFINITY
+    };
+    tx.min(ty).max(0.0)
+}
+
+/// Nearest food, creature or wall along the ray, `walls` is the world size if it has any
+pub fn cast<'a>(
+    origin: Vec2,
+    dir: Vec2,
+    range: f32,
+    walls: Option<(f32, f32)>,
+    world: &WorldMap,
+    food: impl Iterator<Item = &'a (f32, f32)>,
+    creatures: impl Iterator<Item = &'a ((f32, f32), HitKind)>,
+) -> Hit {
+    let mut nearest = (range, HitKind::Nothing);
+    let mut check = |t: Option<f32>, kind: HitKind| {
+        if let Some(t) = t {
+            if t < nearest.0 {
+                nearest = (t, kind);
+            }
+        }
+    };
+
+    check(walls.map(|w| ray_wall(origin, dir, w)), HitKind::Wall);
+    check(world.ray(origin, dir), HitKind::Obstacle);
+    for f in food {
+        check(
+            ray_circle(origin, dir, Vec2::new(f.0, f.1), CREATURE_DIM_HALF),
+            HitKind::Food,
+        );
+    }
+    for (c, kind) in creatures {
+        check(
+            ray_circle(origin, dir, Vec2::new(c.0, c.1), CREATURE_DIM_HALF),
+            *kind,
+        );
+    }
+
+    Hit {
+        distance: if range > 0.0 { nearest.0 / range } else { 1.0 },
+        kind: nearest.1,
+    }
+}
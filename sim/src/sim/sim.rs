@@ -1,156 +1,592 @@
-use std::{thread::sleep, time::Duration};
+use std::{
+    collections::{hash_map::DefaultHasher, HashSet},
+    f32::consts::TAU,
+    hash::BuildHasherDefault,
+    sync::Arc,
+    thread::sleep,
+    time::{Duration, Instant},
+};
 
+use bevy::math::Vec2;
 use dashmap::DashMap;
-use engine::nn::{Net, Node};
+use engine::nn::{
+    mutate::{AddEdge, AddNeuron, FixedCount, LinkMutator, NeuronMutator, RemoveEdge},
+    reproduce::{DefaultIterator, NeatCrossover, ParentFitness},
+    BasicNeuron, GraphLocation, Net, Neuron, Node,
+};
+use engine::{rng, NeuronInfo};
 use flume::{unbounded, Receiver, Sender};
 use rand::Rng;
-use rayon::prelude::*;
+use rayon::{iter::Either, prelude::*};
 use serde::{Deserialize, Serialize};
-use bevy::math::Vec2;
+use thiserror::Error;
+
+use crate::inputs::ResourceLevel;
+
+use super::{
+    config::{Experiment, SimConfig},
+    food::{Food, FoodSpawner, ResourceField},
+    grid::Grid,
+    lineage::{FamilyTree, Lineage},
+    recorder::{CreatureEvent, EventKind, Recorder},
+    sensors::{cast, HitKind},
+    snapshot::{Snapshot, SnapshotBuffer},
+    stats::TickStats,
+    world::Boundary,
+};
 
 pub const CREATURE_DIM: f32 = 5.0;
 pub const CREATURE_DIM_HALF: f32 = CREATURE_DIM / 2.0;
+const FAMILY_TREE_DEPTH: usize = 3;
+const GRID_CELL_SIZE: f32 = 25.0;
+
+// output layer indices
+const THRUST: usize = 0;
+const TURN: usize = 1;
+const MATE: usize = 2;
+const EAT: usize = 3;
+const ATTACK: usize = 4;
+
+/// Creatures by id, iterated in the same order every run so a seed replays it
+type Creatures = DashMap<usize, Creature, BuildHasherDefault<DefaultHasher>>;
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 struct Simulation {
     world_dim: (f32, f32),
-    creatures: DashMap<usize, Creature>,
-    food: Vec<(f32, f32)>,
+    creatures: Creatures,
+    food: Vec<Food>,
+    spawner: FoodSpawner,
+    field: Option<ResourceField>,
+    config: SimConfig,
+    /// Input layout of every species, in the same order as `SimConfig::species`
+    layouts: Vec<Layout>,
     last_id: usize,
     ticks: usize,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+struct Layout {
+    /// Input nodes of a newborn, children start from these instead of their parents' values
+    base_inputs: Vec<Node>,
+    output_nodes: Vec<Node>,
+    /// Index of the first ray input, every ray has a distance followed by a hit input
+    sensor_inputs: usize,
+    field_input: Option<usize>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 struct Creature {
     brain: Net,
+    species: usize,
     position: (f32, f32),
+    /// Radians counter clockwise from +x
+    heading: f32,
+    parents: Option<(usize, usize)>,
+    generation: usize,
+    birth_tick: usize,
+    last_mated: Option<usize>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct BasicCreature {
+    pub species: usize,
     pub position: (f32, f32),
+    pub heading: f32,
     pub id: usize,
+    pub parents: Option<(usize, usize)>,
+    pub generation: usize,
+    pub birth_tick: usize,
+    pub hunger: f32,
+    pub health: f32,
+}
+
+/// Live state of a single creature for the selection panel
+#[derive(Debug, Clone, Default)]
+pub struct Readout {
+    pub creature: BasicCreature,
+    pub age: f32,
+    /// Input and output neurons by label, in layer order
+    pub inputs: Vec<(String, f32)>,
+    pub outputs: Vec<(String, f32)>,
+}
+
+/// Where a creature ended up after moving and what it wants to do there
+#[derive(Debug, Clone)]
+struct Body {
+    id: usize,
+    species: usize,
+    position: (f32, f32),
+    mate: bool,
+    eat: bool,
+    attack: bool,
+}
+
+/// Creatures born and died during a single tick
+#[derive(Debug, Clone, Default)]
+struct TickEvents {
+    births: Vec<usize>,
+    /// Dead creatures and where they died
+    deaths: Vec<(usize, (f32, f32))>,
+    /// Creatures that ate a food item and where
+    eats: Vec<(usize, (f32, f32))>,
 }
 
 pub struct Runner {
     rx: Receiver<RunnerReq>,
-    tx: Sender<RunnerRes>,
+    tx: Sender<Reply>,
+    stats: Sender<TickStats>,
+    snapshot: Arc<SnapshotBuffer>,
     sim: Simulation,
+    lineage: Lineage,
+    /// Kills and clones since the last tick, counted and recorded with the next tick
+    manual_events: TickEvents,
+    /// Writes the run to disk when the simulation was created with recording enabled
+    recorder: Option<Recorder>,
+    record_status: Option<String>,
+    /// Creature whose readout is published with every snapshot
+    selected: Option<usize>,
     paused: bool,
+    tick_rate: TickRate,
+    /// When the next tick is due when running at a target rate
+    next_tick: Instant,
+    /// Ticks per second measured over the last second
+    tps: f32,
+    window_start: Instant,
+    window_ticks: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum TickRate {
+    /// Ticks per second
+    Target(f32),
+    Unlimited,
+}
+
+impl Default for TickRate {
+    fn default() -> Self {
+        TickRate::Target(60.0)
+    }
 }
 
 #[derive(Debug, Clone)]
 pub enum RunnerReq {
-    Generate(Generate),
+    Generate(Experiment),
     Resume,
     Pause,
-    GetPositions,
-    GetNet(usize),
+    /// Runs a single tick while paused
+    Step,
+    SetTickRate(TickRate),
+    GetNet(RequestId, usize),
+    GetFamilyTree(RequestId, usize),
+    GetLivingDescendants(RequestId, usize),
+    /// Replaces a creature's brain, e.g. after editing it by hand
+    SetNet(RequestId, usize, Net),
+    /// Sets whose readout is published with the snapshots
+    Select(Option<usize>),
+    Kill(usize),
+    /// Adds a copy of the creature next to it, recorded as its only parent
+    Clone(usize),
 }
 
+/// Ties a reply to the request that asked for it
+pub type RequestId = u64;
+
 #[derive(Debug, Clone)]
 pub enum RunnerRes {
-    Positions(Positions),
     Net(Option<Net>),
+    FamilyTree(Option<FamilyTree>),
+    LivingDescendants(HashSet<usize>),
+    NetSet(Result<(), SetNetError>),
 }
 
-#[derive(Debug, Clone)]
-pub struct Positions {
-    pub creatures: Vec<BasicCreature>,
-    pub food: Vec<(f32, f32)>,
+#[derive(Debug, Clone, Error)]
+pub enum SetNetError {
+    #[error("Creature {0} is no longer alive")]
+    NotFound(usize),
+    #[error("Inputs and outputs must stay the same as the creature's")]
+    LayoutChanged,
+    #[error("The net has a cycle")]
+    Cycle,
 }
 
 #[derive(Debug, Clone)]
-pub struct Generate {
-    pub num_creatures: usize,
-    pub input_nodes: Vec<Node>,
-    pub output_nodes: Vec<Node>,
-    pub dims: (f32, f32),
+pub struct Reply {
+    pub id: RequestId,
+    pub res: RunnerRes,
 }
 
 impl Runner {
-    pub fn new() -> (Self, Sender<RunnerReq>, Receiver<RunnerRes>) {
+    pub fn new() -> (
+        Self,
+        Sender<RunnerReq>,
+        Receiver<Reply>,
+        Receiver<TickStats>,
+        Arc<SnapshotBuffer>,
+    ) {
         let (tx_req, rx_req) = unbounded();
         let (tx_res, rx_res) = unbounded();
+        let (tx_stats, rx_stats) = unbounded();
+        let snapshot = Arc::new(SnapshotBuffer::default());
         let r = Runner {
             rx: rx_req,
             tx: tx_res,
+            stats: tx_stats,
+            snapshot: snapshot.clone(),
             sim: Simulation::default(),
+            lineage: Lineage::default(),
+            manual_events: TickEvents::default(),
+            recorder: None,
+            record_status: None,
+            selected: None,
             paused: true,
+            tick_rate: TickRate::default(),
+            next_tick: Instant::now(),
+            tps: 0.0,
+            window_start: Instant::now(),
+            window_ticks: 0,
         };
-        (r, tx_req, rx_res)
+        (r, tx_req, rx_res, rx_stats, snapshot)
     }
 
     pub fn run(mut self) {
         loop {
             if self.rx.len() > 0 {
-                self.rx.drain().for_each(|msg| match msg {
-                    RunnerReq::Generate(g) => {
-                        let mut rng = rand::thread_rng();
-                        let width = g.dims.0;
-                        let height = g.dims.1;
-                        self.sim.creatures = (0..g.num_creatures)
-                            .map(|_| {
-                                self.sim.last_id += 1;
-                                (
-                                    self.sim.last_id,
-                                    Creature {
-                                        brain: Net::gen(&g.input_nodes, &g.output_nodes).unwrap(),
-                                        position: (
-                                            rng.gen_range(0.0..width),
-                                            rng.gen_range(0.0..height),
-                                        ),
-                                    },
-                                )
-                            })
-                            .collect();
-                        self.sim.food = (0..(g.num_creatures / 4))
-                            .map(|_| (rng.gen_range(0.0..width), rng.gen_range(0.0..height)))
-                            .collect();
-                        self.sim.world_dim = g.dims;
+                // drained from a clone, the handlers need `self` mutably
+                let rx = self.rx.clone();
+                rx.drain().for_each(|msg| match msg {
+                    RunnerReq::Generate(experiment) => self.generate(experiment),
+                    RunnerReq::Resume => {
+                        self.paused = false;
+                        self.next_tick = Instant::now();
                     }
-                    RunnerReq::Resume => self.paused = false,
-                    RunnerReq::Pause => self.paused = true,
-                    RunnerReq::GetPositions => self
-                        .tx
-                        .send(RunnerRes::Positions(Positions {
-                            creatures: self
-                                .sim
-                                .creatures
-                                .par_iter()
-                                .map(|x| BasicCreature {
-                                    position: x.value().position,
-                                    id: *x.key(),
-                                })
-                                .collect(),
-                            food: self.sim.food.clone(),
-                        }))
-                        .expect("Could not send positions"),
-                    RunnerReq::GetNet(id) => {
-                        match self.sim.creatures.iter().find(|c| *c.key() == id) {
-                            Some(c) => self.tx.send(RunnerRes::Net(Some(c.brain.clone()))),
-                            None => self.tx.send(RunnerRes::Net(None)),
+                    RunnerReq::Pause => {
+                        self.paused = true;
+                        self.flush_recording();
+                    }
+                    RunnerReq::Step => {
+                        if self.paused {
+                            self.tick();
+                        }
+                    }
+                    RunnerReq::SetTickRate(rate) => {
+                        self.tick_rate = rate;
+                        self.next_tick = Instant::now();
+                    }
+                    RunnerReq::GetNet(req, id) => self.reply(
+                        req,
+                        RunnerRes::Net(self.sim.creatures.get(&id).map(|c| c.brain.clone())),
+                    ),
+                    RunnerReq::GetFamilyTree(req, id) => self.reply(
+                        req,
+                        RunnerRes::FamilyTree(self.lineage.family_tree(id, FAMILY_TREE_DEPTH)),
+                    ),
+                    RunnerReq::GetLivingDescendants(req, id) => self.reply(
+                        req,
+                        RunnerRes::LivingDescendants(self.lineage.living_descendants(id)),
+                    ),
+                    RunnerReq::SetNet(req, id, net) => {
+                        let res = self.sim.set_net(id, net);
+                        self.reply(req, RunnerRes::NetSet(res));
+                    }
+                    RunnerReq::Select(id) => {
+                        self.selected = id;
+                        self.publish();
+                    }
+                    RunnerReq::Kill(id) => {
+                        if let Some((_, c)) = self.sim.creatures.remove(&id) {
+                            self.lineage.death(id, self.sim.ticks);
+                            self.manual_events.deaths.push((id, c.position));
+                            self.publish();
                         }
-                        .expect("Could not send net")
+                    }
+                    RunnerReq::Clone(id) => {
+                        let clone = self.sim.clone_creature(id);
+                        if let Some(c) = clone.and_then(|clone| self.sim.creatures.get(&clone)) {
+                            self.lineage
+                                .birth(*c.key(), c.parents, c.generation, c.birth_tick);
+                            self.manual_events.births.push(*c.key());
+                        }
+                        self.publish();
                     }
                 });
             }
 
+            self.measure_tps();
             if self.paused {
                 sleep(Duration::from_millis(100));
                 continue;
             }
 
-            self.sim.run();
-            self.sim.ticks += 1;
+            if let TickRate::Target(tps) = self.tick_rate {
+                let now = Instant::now();
+                if now < self.next_tick {
+                    // short sleeps keep requests answered quickly at low rates
+                    sleep((self.next_tick - now).min(Duration::from_millis(10)));
+                    continue;
+                }
+                let interval = Duration::from_secs_f32(1.0 / tps.max(f32::EPSILON));
+                self.next_tick += interval;
+                // don't try to catch up after falling behind, e.g. when the rate is too high
+                if self.next_tick < now {
+                    self.next_tick = now + interval;
+                }
+            }
+            self.tick();
+        }
+    }
+
+    /// Runs an experiment for `ticks` ticks on the calling thread, returning the last
+    /// tick's stats and where it was recorded
+    pub fn headless(experiment: Experiment, ticks: usize) -> (TickStats, Option<String>) {
+        // the other ends have to stay open for the runner's sends
+        let (mut r, _tx, _rx, stats, _snapshot) = Runner::new();
+        r.generate(experiment);
+        let mut last = TickStats::default();
+        for _ in 0..ticks {
+            r.tick();
+            last = stats.try_iter().last().unwrap_or(last);
+        }
+        r.flush_recording();
+        (last, r.record_status)
+    }
+
+    fn generate(&mut self, mut e: Experiment) {
+        // everything random on the runner thread, from brains to food, comes from this seed
+        rng::seed(*e.seed.get_or_insert_with(rand::random));
+        let mut rng = rng::thread_rng();
+        let (width, height) = e.dims;
+        let config = &e.sim;
+        self.lineage.clear();
+        self.manual_events = TickEvents::default();
+        self.sim.ticks = 0;
+
+        let food = &config.food;
+        self.sim.field = food
+            .field
+            .enabled
+            .then(|| ResourceField::new(&food.field, e.dims));
+        self.sim.layouts = config
+            .species
+            .iter()
+            .map(|sp| {
+                let t = sp.template.as_ref().unwrap_or(&e.template);
+                Layout::new(&t.input_nodes, &t.output_nodes, config)
+            })
+            .collect();
+
+        let total = config
+            .species
+            .iter()
+            .map(|sp| sp.share.max(0.0))
+            .sum::<f32>();
+        self.sim.creatures = Creatures::default();
+        for (species, sp) in config.species.iter().enumerate() {
+            let layout = &self.sim.layouts[species];
+            let count = if total > 0.0 {
+                (e.num_creatures as f32 * sp.share.max(0.0) / total).round() as usize
+            } else {
+                0
+            };
+            for _ in 0..count {
+                self.sim.last_id += 1;
+                self.lineage.birth(self.sim.last_id, None, 0, 0);
+                let mut brain = Net::gen(&layout.base_inputs, &layout.output_nodes).unwrap();
+                brain.enable_provenance();
+                self.sim.creatures.insert(
+                    self.sim.last_id,
+                    Creature {
+                        brain,
+                        species,
+                        position: (rng.gen_range(0.0..width), rng.gen_range(0.0..height)),
+                        heading: rng.gen_range(0.0..TAU),
+                        ..Default::default()
+                    },
+                );
+            }
+        }
+        self.sim.spawner = FoodSpawner::new(food, e.dims, &mut rng);
+        self.sim.food = (0..(e.num_creatures as f32 * food.initial_per_creature) as usize)
+            .filter_map(|_| {
+                self.sim
+                    .spawner
+                    .spawn(food, &config.world, e.dims, &mut rng)
+            })
+            .collect();
+        self.sim.world_dim = e.dims;
+        self.start_recording(&e);
+        self.sim.config = e.sim;
+        self.publish();
+    }
+
+    fn tick(&mut self) {
+        let mut events = self.sim.run();
+        for id in events.births.iter() {
+            if let Some(c) = self.sim.creatures.get(id) {
+                self.lineage
+                    .birth(*id, c.parents, c.generation, c.birth_tick);
+            }
+        }
+        for (id, _) in events.deaths.iter() {
+            self.lineage.death(*id, self.sim.ticks);
+        }
+        // already in the lineage, only the stats and the recording still need them
+        let manual = std::mem::take(&mut self.manual_events);
+        events.births.extend(manual.births);
+        events.deaths.extend(manual.deaths);
+        self.sim.ticks += 1;
+        self.window_ticks += 1;
+        let stats = self.sim.stats(&events);
+        self.record(&stats, &events);
+        self.stats.send(stats).expect("Could not send stats");
+        self.publish();
+    }
+
+    fn start_recording(&mut self, experiment: &Experiment) {
+        self.recorder = None;
+        self.record_status = None;
+        let config = &experiment.sim.record;
+        if !config.enabled {
+            return;
+        }
+        match Recorder::create(config, experiment) {
+            Ok(r) => {
+                self.record_status = Some(format!("Recording to {}", r.dir.display()));
+                self.recorder = Some(r);
+            }
+            Err(e) => self.record_status = Some(e.to_string()),
+        }
+    }
+
+    fn flush_recording(&mut self) {
+        if let Some(Err(e)) = self.recorder.as_mut().map(|r| r.flush()) {
+            self.stop_recording(e);
+        }
+    }
+
+    fn stop_recording(&mut self, e: impl std::fmt::Display) {
+        self.recorder = None;
+        self.record_status = Some(format!("Recording stopped: {e}"));
+    }
+
+    fn record(&mut self, stats: &TickStats, events: &TickEvents) {
+        let Some(recorder) = self.recorder.as_mut() else {
+            return;
+        };
+
+        let mut log = Vec::new();
+        for id in events.births.iter() {
+            let Some(c) = self.sim.creatures.get(id) else {
+                continue;
+            };
+            // clones have their original as both parents without having mated
+            if let Some((a, b)) = c.parents.filter(|(a, b)| a != b) {
+                log.push(CreatureEvent {
+                    kind: EventKind::Mate,
+                    id: a,
+                    other: Some(b),
+                    position: c.position,
+                });
+            }
+            log.push(CreatureEvent {
+                kind: EventKind::Birth,
+                id: *id,
+                other: None,
+                position: c.position,
+            });
+        }
+        for (kind, list) in [
+            (EventKind::Eat, &events.eats),
+            (EventKind::Death, &events.deaths),
+        ] {
+            log.extend(list.iter().map(|(id, position)| CreatureEvent {
+                kind,
+                id: *id,
+                other: None,
+                position: *position,
+            }));
+        }
+
+        let creatures = if recorder.wants_creatures(stats.tick) {
+            self.sim.basic_creatures()
+        } else {
+            Vec::new()
+        };
+        if let Err(e) = recorder.record(stats, &log, &creatures) {
+            self.stop_recording(e);
+        }
+    }
+
+    fn reply(&self, id: RequestId, res: RunnerRes) {
+        self.tx
+            .send(Reply { id, res })
+            .expect("Could not send reply");
+    }
+
+    fn publish(&self) {
+        self.snapshot.publish(Snapshot {
+            creatures: self.sim.basic_creatures(),
+            food: self.sim.food.clone(),
+            ticks: self.sim.ticks,
+            tps: self.tps,
+            recording: self.record_status.clone(),
+            selected: self.selected,
+            readout: self.selected.and_then(|id| self.sim.readout(id)),
+            brain: (self.selected)
+                .and_then(|id| self.sim.creatures.get(&id).map(|c| c.brain.clone())),
+        });
+    }
+
+    fn measure_tps(&mut self) {
+        let elapsed = self.window_start.elapsed();
+        if elapsed >= Duration::from_secs(1) {
+            self.tps = self.window_ticks as f32 / elapsed.as_secs_f32();
+            self.window_start = Instant::now();
+            self.window_ticks = 0;
+        }
+    }
+}
+
+impl Layout {
+    fn new(input_nodes: &[Node], output_nodes: &[Node], config: &SimConfig) -> Layout {
+        let first_id = input_nodes
+            .iter()
+            .chain(output_nodes.iter())
+            .map(|n| n.id() + 1)
+            .max()
+            .unwrap_or(0);
+        let mut base_inputs = input_nodes.to_vec();
+        base_inputs.extend(config.sensors.input_nodes(first_id));
+
+        let mut field_input = None;
+        if config.food.field.enabled {
+            field_input = Some(base_inputs.len());
+            base_inputs.push(Node::Input(ResourceLevel::new(
+                1.0,
+                first_id + config.sensors.rays * 2,
+            )));
+        }
+
+        Layout {
+            base_inputs,
+            output_nodes: output_nodes.to_vec(),
+            sensor_inputs: input_nodes.len(),
+            field_input,
         }
     }
 }
 
-fn dirs_to_vec(forward: f32, backward: f32, left: f32, right: f32) -> Vec2 {
-    let x = Vec2::new(right, 0.0) + Vec2::new(left * -1.0, 0.0);
-    let y = Vec2::new(0.0, forward) + Vec2::new(0.0, backward * -1.0);
-    x + y
+fn basic_creature(id: usize, c: &Creature) -> BasicCreature {
+    BasicCreature {
+        species: c.species,
+        position: c.position,
+        heading: c.heading,
+        id,
+        parents: c.parents,
+        generation: c.generation,
+        birth_tick: c.birth_tick,
+        hunger: input_value(c, 0),
+        health: input_value(c, 2),
+    }
 }
 
 fn get_output_value(value: &Node) -> f32 {
@@ -161,122 +597,511 @@ fn get_output_value(value: &Node) -> f32 {
     }
 }
 
-fn do_squares_collide(a: (f32, f32), b: (f32, f32)) -> bool {
-    let a_min_x = a.0 - CREATURE_DIM_HALF;
-    let a_max_x = a.0 + CREATURE_DIM_HALF;
-    let a_min_y = a.1 - CREATURE_DIM_HALF;
-    let a_max_y = a.1 + CREATURE_DIM_HALF;
+fn wants_to_mate(c: &Creature, ticks: usize, cooldown: usize) -> bool {
+    wants_to(c, MATE) && c.last_mated.is_none_or(|t| ticks - t >= cooldown)
+}
+
+fn wants_to(c: &Creature, output: usize) -> bool {
+    let output_layer = &c.brain.graph.layers[c.brain.graph.layers.len() - 1];
+    get_output_value(&output_layer[output].value) > 0.5
+}
+
+/// Lowers health by `damage`, the creature dies at the end of the tick if it drops to zero
+fn hurt(c: &mut Creature, damage: f32) {
+    let input_layer = c.brain.input_layer as usize;
+    if let Node::Input(n) = &mut c.brain.graph.layers[input_layer][2].value {
+        n.set_value(n.as_standard() - damage);
+    }
+}
+
+fn kill(c: &mut Creature) {
+    let input_layer = c.brain.input_layer as usize;
+    if let Node::Input(n) = &mut c.brain.graph.layers[input_layer][2].value {
+        n.set_value(0.0);
+    }
+}
+
+/// Lowers hunger by `nutrition`, never below zero
+fn feed(c: &mut Creature, nutrition: f32) {
+    let input_layer = c.brain.input_layer as usize;
+    if let Node::Input(n) = &mut c.brain.graph.layers[input_layer][0].value {
+        let v = n.as_standard() - nutrition;
+        n.set_value(if v < 0.0 { 0.0 } else { v });
+    }
 
-    let b_min_x = b.0 - CREATURE_DIM_HALF;
-    let b_max_x = b.0 + CREATURE_DIM_HALF;
-    let b_min_y = b.1 - CREATURE_DIM_HALF;
-    let b_max_y = b.1 + CREATURE_DIM_HALF;
+    if let Node::Input(n) = &mut c.brain.graph.layers[input_layer][3].value {
+        n.set_value(0.0);
+    }
+}
 
-    a_min_x < b_max_x && a_max_x > b_min_x && a_min_y < b_max_y && a_max_y > b_min_y
+fn input_value(c: &Creature, idx: usize) -> f32 {
+    match &c.brain.graph.layers[c.brain.input_layer as usize][idx].value {
+        Node::Input(i) => i.as_standard(),
+        _ => 0.0,
+    }
 }
 
 impl Simulation {
-    fn run(&mut self) {
+    fn basic_creatures(&self) -> Vec<BasicCreature> {
+        self.creatures
+            .par_iter()
+            .map(|x| basic_creature(*x.key(), x.value()))
+            .collect()
+    }
+
+    fn readout(&self, id: usize) -> Option<Readout> {
+        let c = self.creatures.get(&id)?;
+        let layers = &c.brain.graph.layers;
+        let values = |layer: usize| {
+            layers[layer]
+                .iter()
+                .map(|n| match &n.value {
+                    Node::Input(i) => (format!("{} #{}", i._type(), i.id()), i.as_standard()),
+                    Node::Output(o) => (o.name().unwrap_or(o._type()).to_owned(), o.value()),
+                    n => (n._type().to_owned(), 0.0),
+                })
+                .collect()
+        };
+
+        Some(Readout {
+            creature: basic_creature(id, &c),
+            age: input_value(&c, 1),
+            inputs: values(c.brain.input_layer as usize),
+            outputs: values(c.brain.output_layer as usize),
+        })
+    }
+
+    fn clone_creature(&mut self, id: usize) -> Option<usize> {
+        let mut c = self.creatures.get(&id)?.clone();
+        c.parents = Some((id, id));
+        c.generation += 1;
+        c.birth_tick = self.ticks;
+        c.last_mated = Some(self.ticks);
+        c.position.0 += CREATURE_DIM;
+        // out of the world or in an obstacle, the clone shares its original's place
+        let mut heading = c.heading;
+        if !self
+            .config
+            .boundary
+            .apply(&mut c.position, &mut heading, self.world_dim)
+            || self.config.world.blocked(c.position)
+        {
+            c.position.0 -= CREATURE_DIM;
+        }
+
+        self.last_id += 1;
+        self.creatures.insert(self.last_id, c);
+        Some(self.last_id)
+    }
+
+    fn stats(&self, events: &TickEvents) -> TickStats {
+        let creatures = self
+            .creatures
+            .par_iter()
+            .map(|c| {
+                let nodes = c.brain.graph.layers.iter().flatten();
+                (
+                    c.species,
+                    input_value(&c, 0),
+                    input_value(&c, 2),
+                    nodes
+                        .clone()
+                        .filter(|n| !matches!(n.value, Node::None))
+                        .count(),
+                    nodes
+                        .flat_map(|n| n.connections.iter())
+                        .filter(|e| e.value.enabled)
+                        .count(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let population = creatures.len();
+        let mean = |sum: f32| {
+            if population > 0 {
+                sum / population as f32
+            } else {
+                0.0
+            }
+        };
+        TickStats {
+            tick: self.ticks,
+            population,
+            births: events.births.len(),
+            deaths: events.deaths.len(),
+            food: self.food.len(),
+            mean_health: mean(creatures.iter().map(|c| c.2).sum()),
+            max_health: creatures.iter().map(|c| c.2).fold(0.0, f32::max),
+            mean_hunger: mean(creatures.iter().map(|c| c.1).sum()),
+            mean_nodes: mean(creatures.iter().map(|c| c.3 as f32).sum()),
+            mean_edges: mean(creatures.iter().map(|c| c.4 as f32).sum()),
+            species: creatures.iter().map(|c| c.0).collect::<HashSet<_>>().len(),
+        }
+    }
+
+    fn set_net(&self, id: usize, net: Net) -> Result<(), SetNetError> {
+        let mut c = self
+            .creatures
+            .get_mut(&id)
+            .ok_or(SetNetError::NotFound(id))?;
+        let ids = |n: &Net, layer: u16| {
+            n.graph.layers[layer as usize]
+                .iter()
+                .map(|x| (x.value._type(), x.value.id()))
+                .collect::<Vec<_>>()
+        };
+        if ids(&net, net.input_layer) != ids(&c.brain, c.brain.input_layer)
+            || ids(&net, net.output_layer) != ids(&c.brain, c.brain.output_layer)
+        {
+            return Err(SetNetError::LayoutChanged);
+        }
+        let input_layer = net.input_layer;
+        if (0..net.graph.layers[input_layer as usize].len()).any(|n| {
+            net.graph
+                .has_cycle(Some(GraphLocation::new(input_layer, n as u16)))
+        }) {
+            return Err(SetNetError::Cycle);
+        }
+
+        c.brain = net;
+        Ok(())
+    }
+
+    fn collide(&self, a: (f32, f32), b: (f32, f32)) -> bool {
+        let d = self.config.boundary.delta(a, b, self.world_dim);
+        d.x.abs() < CREATURE_DIM && d.y.abs() < CREATURE_DIM
+    }
+
+    /// Position of `p` as seen from `origin`, which may lie outside the world when wrapping
+    fn nearest_image(&self, origin: (f32, f32), p: (f32, f32)) -> (f32, f32) {
+        let d = self.config.boundary.delta(origin, p, self.world_dim);
+        (origin.0 + d.x, origin.1 + d.y)
+    }
+
+    fn mate(&mut self, a_id: usize, b_id: usize) -> Option<usize> {
+        let a = self.creatures.get(&a_id)?.clone();
+        let b = self.creatures.get(&b_id)?.clone();
+
+        let link_mutators: Vec<Box<dyn LinkMutator>> =
+            vec![Box::new(AddEdge), Box::new(RemoveEdge)];
+        let neuron_mutators: Vec<Box<dyn NeuronMutator>> = vec![Box::new(AddNeuron)];
+        let neurons: Vec<Box<dyn Neuron>> = vec![Box::new(BasicNeuron::default())];
+
+        let mut brain = Net::reproduce(
+            &a.brain,
+            &b.brain,
+            ParentFitness::new(input_value(&a, 2), input_value(&b, 2)),
+            &[NeatCrossover],
+            DefaultIterator::new(),
+        )
+        .ok()?;
+        // experiments with no mutations don't pass `Experiment::errors`
+        let generator = FixedCount::new(self.config.evolution.mutations).ok()?;
+        brain
+            .mutate(
+                &link_mutators,
+                &neuron_mutators,
+                &neurons,
+                &|n| rng::thread_rng().gen_range(0..n),
+                generator,
+            )
+            .ok()?;
+        for (node, base) in brain.graph.layers[brain.input_layer as usize]
+            .iter_mut()
+            .zip(self.layouts[a.species].base_inputs.iter())
+        {
+            node.value = base.clone();
+        }
+
+        for id in [a_id, b_id] {
+            if let Some(mut c) = self.creatures.get_mut(&id) {
+                c.last_mated = Some(self.ticks);
+                let input_layer = c.brain.input_layer as usize;
+                if let Node::Input(i) = &mut c.brain.graph.layers[input_layer][0].value {
+                    i.set_value(i.as_standard() + self.config.evolution.mate_hunger_cost);
+                }
+            }
+        }
+
+        self.last_id += 1;
+        self.creatures.insert(
+            self.last_id,
+            Creature {
+                brain,
+                species: a.species,
+                position: (
+                    (a.position.0 + b.position.0) / 2.0,
+                    (a.position.1 + b.position.1) / 2.0,
+                ),
+                heading: rng::thread_rng().gen_range(0.0..TAU),
+                parents: Some((a_id, b_id)),
+                generation: a.generation.max(b.generation) + 1,
+                birth_tick: self.ticks,
+                last_mated: Some(self.ticks),
+            },
+        );
+        Some(self.last_id)
+    }
+
+    fn run(&mut self) -> TickEvents {
+        let mut events = TickEvents::default();
+
+        let positions = self
+            .creatures
+            .iter()
+            .map(|c| (*c.key(), c.position, c.species))
+            .collect::<Vec<_>>();
+        let creature_grid = Grid::new(
+            self.world_dim,
+            GRID_CELL_SIZE,
+            self.config.boundary == Boundary::Wrap,
+            positions.iter().map(|p| &p.1),
+        );
+        let food_grid = Grid::new(
+            self.world_dim,
+            GRID_CELL_SIZE,
+            self.config.boundary == Boundary::Wrap,
+            self.food.iter().map(|f| &f.position),
+        );
+
+        let dt = self.config.dt;
+        let evolution = &self.config.evolution;
         self.creatures.par_iter_mut().for_each(|mut accessor| {
+            let id = *accessor.key();
             let c = accessor.value_mut();
-            let speed = c.brain.graph.layers[c.brain.graph.layers.len() - 1][4]
+            let species = &self.config.species[c.species];
+            let layout = &self.layouts[c.species];
+            let speed = c.brain.graph.layers[c.brain.graph.layers.len() - 1][THRUST]
                 .value
                 .clone();
+            let (speed_factor, terrain_damage) = self.config.world.terrain_effects(c.position);
             let inputs = &mut c.brain.graph.layers[c.brain.input_layer as usize];
+            let mut starving = false;
             if let Node::Input(i) = &mut inputs[0].value {
-                i.set_value(i.as_standard() + 0.01); // hunger
+                i.set_value(i.as_standard() + species.metabolism * dt); // hunger
+                starving = i.as_standard() > evolution.max_hunger;
             }
 
-            if let Node::Input(i) = &mut inputs[0].value {
-                i.set_value(i.as_standard() + 1.0); // age
+            if let Node::Input(i) = &mut inputs[1].value {
+                i.set_value(i.as_standard() + dt); // age
             }
 
-            if let Node::Input(i) = &mut inputs[0].value {
-                i.set_value(i.as_standard() - 0.00001); // health
+            if let Node::Input(i) = &mut inputs[2].value {
+                let damage = terrain_damage
+                    + if starving {
+                        evolution.starvation_damage
+                    } else {
+                        0.0
+                    };
+                i.set_value(i.as_standard() - (0.00001 + damage) * dt); // health
             }
 
-            if let Node::Input(i) = &mut inputs[0].value {
+            if let Node::Input(i) = &mut inputs[3].value {
                 if let Node::Output(o) = speed {
                     i.set_value(o.value()); // speed
                 }
             }
 
+            let near_food = food_grid
+                .query(c.position, self.config.sensors.range)
+                .map(|i| self.nearest_image(c.position, self.food[i].position))
+                .collect::<Vec<_>>();
+            let near_creatures = creature_grid
+                .query(c.position, self.config.sensors.range)
+                .filter(|i| positions[*i].0 != id)
+                .map(|i| {
+                    let kind = if positions[i].2 == c.species {
+                        HitKind::Creature
+                    } else {
+                        HitKind::Stranger
+                    };
+                    (self.nearest_image(c.position, positions[i].1), kind)
+                })
+                .collect::<Vec<_>>();
+
+            let origin = Vec2::new(c.position.0, c.position.1);
+            let sensors = &self.config.sensors;
+            for ray in 0..sensors.rays {
+                let dir = Vec2::from_angle(c.heading + sensors.ray_angle(ray));
+                let hit = cast(
+                    origin,
+                    dir,
+                    sensors.range,
+                    self.config.boundary.has_walls().then_some(self.world_dim),
+                    &self.config.world,
+                    near_food.iter(),
+                    near_creatures.iter(),
+                );
+
+                let idx = layout.sensor_inputs + ray * 2;
+                if let Node::Input(i) = &mut inputs[idx].value {
+                    i.set_value(hit.distance);
+                }
+                if let Node::Input(i) = &mut inputs[idx + 1].value {
+                    i.set_value(hit.kind.as_input());
+                }
+            }
+
+            if let (Some(idx), Some(field)) = (layout.field_input, self.field.as_ref()) {
+                if let Node::Input(i) = &mut inputs[idx].value {
+                    i.set_value(field.sample(c.position) / self.config.food.field.max);
+                }
+            }
+
             c.brain.tick();
 
             let output_layer = &c.brain.graph.layers[c.brain.graph.layers.len() - 1];
-            let forward = get_output_value(&output_layer[0].value);
-            let backward = get_output_value(&output_layer[1].value);
-            let left = get_output_value(&output_layer[2].value);
-            let right = get_output_value(&output_layer[3].value);
+            let thrust = get_output_value(&output_layer[THRUST].value);
+            // sigmoid output, below 0.5 turns clockwise and above counter clockwise
+            let turn = (get_output_value(&output_layer[TURN].value) - 0.5) * 2.0;
+
+            c.heading = (c.heading + turn * species.turn_rate * dt).rem_euclid(TAU);
+            let t = Vec2::from_angle(c.heading) * thrust * species.max_speed * speed_factor * dt;
+
+            let mut position = (c.position.0 + t.x, c.position.1 + t.y);
+            let alive = self
+                .config
+                .boundary
+                .apply(&mut position, &mut c.heading, self.world_dim);
+            c.heading = c.heading.rem_euclid(TAU);
+            if !alive {
+                kill(c);
+            }
+            // obstacles stop the creature where it was
+            if !self.config.world.blocked(position) {
+                c.position = position;
+            }
+        });
 
-            let movement_vec = dirs_to_vec(forward, backward, left, right);
-            let speed = get_output_value(&output_layer[4].value);
+        let bodies = self
+            .creatures
+            .iter()
+            .map(|c| {
+                let diet = self.config.species[c.species].diet;
+                Body {
+                    id: *c.key(),
+                    species: c.species,
+                    position: c.position,
+                    mate: wants_to_mate(&c, self.ticks, self.config.evolution.mate_cooldown),
+                    eat: diet.eats_food() && wants_to(&c, EAT),
+                    attack: wants_to(&c, ATTACK),
+                }
+            })
+            .collect::<Vec<_>>();
+        let grid = Grid::new(
+            self.world_dim,
+            GRID_CELL_SIZE,
+            self.config.boundary == Boundary::Wrap,
+            bodies.iter().map(|b| &b.position),
+        );
 
-            let t = movement_vec * speed; // add time diff here if needed
+        let pairs = bodies
+            .par_iter()
+            .filter(|b| b.mate)
+            .filter_map(|b| {
+                grid.query(b.position, CREATURE_DIM)
+                    .map(|i| &bodies[i])
+                    .find(|x| {
+                        x.id > b.id
+                            && x.mate
+                            && x.species == b.species
+                            && self.collide(x.position, b.position)
+                    })
+                    .map(|x| (b.id, x.id))
+            })
+            .collect::<Vec<_>>();
 
-            c.position.0 += t.x;
-            c.position.1 += t.y;
+        // a creature mates at most once per tick
+        let mut mated = HashSet::new();
+        for (a, b) in pairs {
+            if mated.contains(&a) || mated.contains(&b) {
+                continue;
+            }
+            if let Some(child) = self.mate(a, b) {
+                mated.insert(a);
+                mated.insert(b);
+                events.births.push(child);
+            }
+        }
 
-            // wall collisions
-            if c.position.0 <= 0.0 {
-                c.position.0 = 0.0;
-            } else if c.position.0 >= self.world_dim.0 {
-                c.position.0 = self.world_dim.0;
+        let attacks = bodies
+            .par_iter()
+            .filter(|b| b.attack)
+            .filter_map(|b| {
+                grid.query(b.position, CREATURE_DIM)
+                    .map(|i| &bodies[i])
+                    .find(|x| x.species != b.species && self.collide(x.position, b.position))
+                    .map(|x| (b, x.id))
+            })
+            .collect::<Vec<_>>();
+        for (attacker, target) in attacks {
+            let species = &self.config.species[attacker.species];
+            if let Some(mut c) = self.creatures.get_mut(&target) {
+                hurt(&mut c, species.attack_damage);
+            }
+            if species.diet.eats_meat() {
+                if let Some(mut c) = self.creatures.get_mut(&attacker.id) {
+                    feed(&mut c, species.attack_damage * species.meat_nutrition);
+                }
             }
+        }
 
-            if c.position.1 <= 0.0 {
-                c.position.1 = 0.0;
-            } else if c.position.1 >= self.world_dim.1 {
-                c.position.1 = self.world_dim.1;
+        let eaten: Vec<(usize, (f32, f32), usize)>;
+        (self.food, eaten) = self.food.par_iter().partition_map(|f| {
+            let eater = grid
+                .query(f.position, CREATURE_DIM)
+                .map(|i| &bodies[i])
+                .find(|b| b.eat && self.collide(b.position, f.position));
+            match eater.filter(|b| self.creatures.contains_key(&b.id)) {
+                Some(b) => Either::Right((b.id, b.position, f.kind)),
+                None => Either::Left(*f),
             }
         });
+        // fed in food order, a creature eating several items adds them up the same way every run
+        for (id, position, kind) in eaten {
+            let nutrition = self
+                .config
+                .food
+                .types
+                .get(kind)
+                .map_or(1.0, |t| t.nutrition);
+            if let Some(mut c) = self.creatures.get_mut(&id) {
+                feed(&mut c, nutrition);
+            }
+            events.eats.push((id, position));
+        }
 
-        self.creatures.par_iter().for_each(|c| {
-            let output_layer = &c.brain.graph.layers[c.brain.graph.layers.len() - 1];
-            if let Some(c_meet) = self
-                .creatures
-                .par_iter()
-                .find_first(|x| do_squares_collide(x.position, c.position))
-            {
-                if get_output_value(&output_layer[5].value) > 0.5
-                    && get_output_value(
-                        &c_meet.brain.graph.layers[c_meet.brain.graph.layers.len() - 1][5].value,
-                    ) > 0.5
-                {
-                    // TODO: mate
+        if let Some(field) = self.field.as_mut() {
+            let config = &self.config.food.field;
+            for b in bodies.iter().filter(|b| b.eat) {
+                let taken = field.take(b.position, config.bite);
+                if let Some(mut c) = self.creatures.get_mut(&b.id) {
+                    feed(&mut c, taken * config.nutrition);
                 }
             }
-        });
+            field.regrow(config);
+        }
+
+        let spawned = self.spawner.tick(
+            &self.config.food,
+            &self.config.world,
+            self.world_dim,
+            self.ticks,
+            self.food.len(),
+            &mut rng::thread_rng(),
+        );
+        self.food.extend(spawned);
 
-        self.food = self
-            .food
+        events.deaths = self
+            .creatures
             .par_iter()
-            .map(|f| {
-                if let Some(mut accessor) = self
-                    .creatures
-                    .par_iter_mut()
-                    .find_first(|x| do_squares_collide(x.position, *f))
-                {
-                    let c_meet = accessor.value();
-                    let output_layer =
-                        &c_meet.brain.graph.layers[c_meet.brain.graph.layers.len() - 1];
-                    if get_output_value(&output_layer[6].value) > 0.5 {
-                        let c_meet = accessor.value_mut();
-                        if let Node::Input(n) = &mut c_meet.brain.graph.layers[0][0].value {
-                            let v = n.as_standard() - 1.0;
-                            n.set_value(if v < 0.0 { 0.0 } else { v });
-                        }
+            .filter(|c| input_value(c, 2) <= 0.0)
+            .map(|c| (*c.key(), c.position))
+            .collect();
+        for (id, _) in events.deaths.iter() {
+            self.creatures.remove(id);
+        }
 
-                        if let Node::Input(n) = &mut c_meet.brain.graph.layers[0][3].value {
-                            n.set_value(0.0);
-                        }
-                        return Err(());
-                    }
-                }
-                return Ok(*f);
-            })
-            .filter(|x| x.is_ok())
-            .map(|x| x.unwrap())
-            .collect::<Vec<_>>();
+        events
     }
 }
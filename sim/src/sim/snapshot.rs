@@ -0,0 +1,52 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc, Mutex,
+};
+
+use engine::nn::Net;
+
+use super::{
+    food::Food,
+    sim::{BasicCreature, Readout},
+};
+
+/// State of the world after a tick, published by the runner for drawing
+#[derive(Debug, Clone, Default)]
+pub struct Snapshot {
+    pub creatures: Vec<BasicCreature>,
+    pub food: Vec<Food>,
+    pub ticks: usize,
+    /// Ticks per second the runner actually reached
+    pub tps: f32,
+    /// Where the run is recorded to, or why it isn't anymore
+    pub recording: Option<String>,
+    /// Creature the readout was asked for, so a stale readout isn't shown for a new selection
+    pub selected: Option<usize>,
+    /// Live state of the selected creature, `None` once it died
+    pub readout: Option<Readout>,
+    /// Brain of the selected creature with its live activations, for the inspector
+    pub brain: Option<Net>,
+}
+
+/// Two snapshot slots, the runner fills the back one and then swaps them so readers
+/// never wait for a tick to finish
+#[derive(Debug, Default)]
+pub struct SnapshotBuffer {
+    slots: [Mutex<Arc<Snapshot>>; 2],
+    front: AtomicUsize,
+}
+
+impl SnapshotBuffer {
+    pub fn publish(&self, snapshot: Snapshot) {
+        let back = 1 - self.front.load(Ordering::Acquire);
+        *self.slots[back].lock().unwrap() = Arc::new(snapshot);
+        self.front.store(back, Ordering::Release);
+    }
+
+    pub fn latest(&self) -> Arc<Snapshot> {
+        self.slots[self.front.load(Ordering::Acquire)]
+            .lock()
+            .unwrap()
+            .clone()
+    }
+}
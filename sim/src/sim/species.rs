@@ -0,0 +1,177 @@
+use engine::{activations::Sigmoid, nn::Node};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::inputs::{Age, Health, Hunger, Speed};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum Diet {
+    #[default]
+    Herbivore,
+    Carnivore,
+    Omnivore,
+}
+
+impl Diet {
+    pub const ALL: [Diet; 3] = [Diet::Herbivore, Diet::Carnivore, Diet::Omnivore];
+
+    pub fn eats_food(self) -> bool {
+        self != Diet::Carnivore
+    }
+
+    pub fn eats_meat(self) -> bool {
+        self != Diet::Herbivore
+    }
+}
+
+/// Inputs every template starts with, the simulation sets them by position
+pub const TEMPLATE_INPUTS: [&str; 4] = ["Hunger", "Age", "Health", "Speed"];
+/// Outputs every template starts with, the simulation reads them by position
+pub const TEMPLATE_OUTPUTS: [&str; 5] = ["thrust", "turn", "mate", "eat", "attack"];
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum TemplateError {
+    #[error("input {0} must be a {1} input")]
+    Input(usize, &'static str),
+    #[error("output {0} must be an output named {1}")]
+    Output(usize, &'static str),
+}
+
+/// Inputs and outputs of a species' brains
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Template {
+    pub input_nodes: Vec<Node>,
+    pub output_nodes: Vec<Node>,
+}
+
+impl Default for Template {
+    fn default() -> Self {
+        Self {
+            input_nodes: vec![
+                Node::Input(Hunger::new(0.0, 0)),
+                Node::Input(Age::new(0, 1)),
+                Node::Input(Health::new(100.0, 2)),
+                Node::Input(Speed::new(0.0, 3)),
+            ],
+            output_nodes: vec![
+                Node::Output(Sigmoid::new(0.0, 4, "thrust".to_string())), // ::<Thrust>
+                Node::Output(Sigmoid::new(0.0, 5, "turn".to_string())),   // ::<Turn>
+                Node::Output(Sigmoid::new(0.0, 6, "mate".to_string())),   // ::<Mate>
+                Node::Output(Sigmoid::new(0.0, 7, "eat".to_string())),    // ::<Eat>
+                Node::Output(Sigmoid::new(0.0, 8, "attack".to_string())), // ::<Attack>
+            ],
+        }
+    }
+}
+
+impl Template {
+    /// Checks the template starts with the inputs and outputs the simulation relies on,
+    /// anything after them is free
+    pub fn check(&self) -> Result<(), TemplateError> {
+        for (idx, expected) in TEMPLATE_INPUTS.into_iter().enumerate() {
+            match self.input_nodes.get(idx) {
+                Some(Node::Input(i)) if i._type() == expected => {}
+                _ => return Err(TemplateError::Input(idx, expected)),
+            }
+        }
+        for (idx, expected) in TEMPLATE_OUTPUTS.into_iter().enumerate() {
+            match self.output_nodes.get(idx) {
+                Some(Node::Output(o)) if o.name() == Some(expected) => {}
+                _ => return Err(TemplateError::Output(idx, expected)),
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Species {
+    pub name: String,
+    pub color: String,
+    /// Relative share of the initial population
+    pub share: f32,
+    pub diet: Diet,
+    /// Distance moved per tick at full thrust
+    pub max_speed: f32,
+    /// Radians turned per tick at full turn output
+    pub turn_rate: f32,
+    /// Hunger gained per tick
+    pub metabolism: f32,
+    /// Health taken from a creature of another species per tick of attacking
+    pub attack_damage: f32,
+    /// Hunger removed per point of damage dealt, only for meat eaters
+    pub meat_nutrition: f32,
+    /// Uses the experiment's template when None. Custom templates must start with
+    /// hunger, age, health and speed inputs and thrust, turn, mate, eat and attack outputs,
+    /// see [`Template::check`]
+    pub template: Option<Template>,
+}
+
+impl Species {
+    pub fn herbivore() -> Species {
+        Species {
+            name: "herbivore".to_owned(),
+            color: "3686ff".to_owned(),
+            share: 0.8,
+            diet: Diet::Herbivore,
+            max_speed: 1.0,
+            turn_rate: 0.2,
+            metabolism: 0.01,
+            attack_damage: 0.0,
+            meat_nutrition: 0.0,
+            template: None,
+        }
+    }
+
+    pub fn carnivore() -> Species {
+        Species {
+            name: "carnivore".to_owned(),
+            color: "b03060".to_owned(),
+            share: 0.2,
+            diet: Diet::Carnivore,
+            max_speed: 1.2,
+            turn_rate: 0.15,
+            metabolism: 0.015,
+            attack_damage: 5.0,
+            meat_nutrition: 0.2,
+            template: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use engine::{activations::Sigmoid, nn::Node};
+
+    use super::{Template, TemplateError};
+    use crate::inputs::Age;
+
+    #[test]
+    fn default_template_is_valid() {
+        assert_eq!(Template::default().check(), Ok(()));
+    }
+
+    #[test]
+    fn extra_nodes_are_allowed() {
+        let mut t = Template::default();
+        t.input_nodes.push(Node::Input(Age::new(0, 9)));
+        t.output_nodes
+            .push(Node::Output(Sigmoid::new(0.0, 10, "extra".to_owned())));
+        assert_eq!(t.check(), Ok(()));
+    }
+
+    #[test]
+    fn missing_or_misplaced_nodes() {
+        let mut t = Template::default();
+        t.input_nodes.truncate(3);
+        assert_eq!(t.check(), Err(TemplateError::Input(3, "Speed")));
+
+        let mut t = Template::default();
+        t.output_nodes.swap(0, 1);
+        assert_eq!(t.check(), Err(TemplateError::Output(0, "thrust")));
+
+        let mut t = Template::default();
+        t.output_nodes.pop();
+        assert_eq!(t.check(), Err(TemplateError::Output(4, "attack")));
+    }
+}
@@ -0,0 +1,53 @@
+use std::{fs::File, io, io::Write, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+/// Population aggregates the runner sends after every tick
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default)]
+pub struct TickStats {
+    pub tick: usize,
+    pub population: usize,
+    pub births: usize,
+    pub deaths: usize,
+    pub food: usize,
+    /// Health is the creatures' energy, starvation, terrain and attacks drain it
+    pub mean_health: f32,
+    pub max_health: f32,
+    pub mean_hunger: f32,
+    /// Brain size, counting only enabled edges
+    pub mean_nodes: f32,
+    pub mean_edges: f32,
+    /// Species with at least one living creature
+    pub species: usize,
+}
+
+impl TickStats {
+    pub const CSV_HEADER: &'static str = "tick,population,births,deaths,food,mean_health,\
+        max_health,mean_hunger,mean_nodes,mean_edges,species";
+
+    pub fn csv_row(&self) -> String {
+        format!(
+            "{},{},{},{},{},{},{},{},{},{},{}",
+            self.tick,
+            self.population,
+            self.births,
+            self.deaths,
+            self.food,
+            self.mean_health,
+            self.max_health,
+            self.mean_hunger,
+            self.mean_nodes,
+            self.mean_edges,
+            self.species
+        )
+    }
+}
+
+pub fn write_csv(path: impl AsRef<Path>, stats: &[TickStats]) -> io::Result<()> {
+    let mut file = io::BufWriter::new(File::create(path)?);
+    writeln!(file, "{}", TickStats::CSV_HEADER)?;
+    for s in stats {
+        writeln!(file, "{}", s.csv_row())?;
+    }
+    file.flush()
+}
@@ -1,38 +1,84 @@
-use std::thread;
+use std::{
+    collections::{HashMap, HashSet},
+    thread,
+};
 
-use bevy::{prelude::*, window::PrimaryWindow};
+use bevy::{
+    input::mouse::{MouseMotion, MouseWheel},
+    prelude::*,
+    render::view::RenderLayers,
+    window::PrimaryWindow,
+};
 use bevy_egui::{
     egui::{self, collapsing_header::CollapsingState, Layout},
     EguiContext,
 };
 use bevy_vector_shapes::prelude::*;
 
-use super::sim::{Generate, Runner, RunnerReq, RunnerRes};
+use super::{
+    config::{EvolutionConfig, Experiment, PRESETS},
+    food::{FoodConfig, FoodType},
+    lineage::FamilyTree,
+    recorder::RecordConfig,
+    sensors::SensorConfig,
+    sim::{BasicCreature, Runner, RunnerReq, RunnerRes, TickRate},
+    species::{Diet, Species},
+    stats::{write_csv, TickStats},
+    world::{Boundary, Shape, TerrainKind, WorldMap},
+};
 
-use crate::{net::resources::InspectNet, BaseNodes, TabState};
+use crate::{
+    net::resources::{ApplyNet, Editor, InspectNet},
+    InspectWindowState, TabState, WorldCamera,
+};
 
 use super::resources::*;
 
+#[derive(Component)]
+pub struct CreatureShape;
+
+#[derive(Component)]
+pub struct FoodShape;
+
+/// Filter for every shape drawn in the world, the inspector's shapes all have render layers
+type WorldShape = (
+    Or<(
+        With<RectangleComponent>,
+        With<TriangleComponent>,
+        With<DiscComponent>,
+    )>,
+    Without<RenderLayers>,
+);
+
+type CreatureShapes<'w, 's> =
+    Query<'w, 's, (&'static mut Transform, &'static mut ShapeFill), With<CreatureShape>>;
+type FoodShapes<'w, 's> = Query<
+    'w,
+    's,
+    (&'static mut Transform, &'static mut ShapeFill),
+    (With<FoodShape>, Without<CreatureShape>),
+>;
+
 const CREATURE_DIM: f32 = 5.0;
 const CREATURE_COLOR: &str = "3686ff";
 const FOOD_COLOR: &str = "54ff71";
+const OBSTACLE_COLOR: &str = "404040";
+const SELECTED_COLOR: &str = "eb4034";
+const DESCENDANT_COLOR: &str = "ffb020";
+const CHART_HEIGHT: f32 = 80.0;
+const MIN_ZOOM: f32 = 0.05;
+const MAX_ZOOM: f32 = 50.0;
 
 pub fn init_runner(mut commands: Commands) {
-    let (r, tx, rx) = Runner::new();
+    let (r, tx, rx, stats, snapshot) = Runner::new();
     let t = thread::spawn(move || {
         r.run();
     });
-    commands.insert_resource(RunnerResource { tx, rx, thread: t });
+    commands.insert_resource(RunnerResource::new(tx, rx, stats, t, snapshot));
 }
 
-pub fn setup(
-    mut control_panel: ResMut<ControlPanel>,
-    window_query: Query<&Window, With<PrimaryWindow>>,
-    mut data: ResMut<Simulation>,
-) {
+pub fn setup(window_query: Query<&Window, With<PrimaryWindow>>, mut data: ResMut<Simulation>) {
     let window = window_query.get_single().unwrap();
-    control_panel.width = window.width().to_string();
-    control_panel.height = window.height().to_string();
     data.window_dims = (window.width(), window.height());
 }
 
@@ -40,14 +86,15 @@ pub fn control_panel(
     mut egui_ctx: Query<&mut EguiContext, With<PrimaryWindow>>,
     mut next_tab_state: ResMut<NextState<TabState>>,
     mut control_panel: ResMut<ControlPanel>,
-    base_nodes: Res<BaseNodes>,
     mut data: ResMut<Simulation>,
     mut next_sim_state: ResMut<NextState<SimulationState>>,
     sim_state: Res<State<SimulationState>>,
     mut commands: Commands,
-    rects: Query<Entity, With<RectangleComponent>>,
+    rects: Query<Entity, WorldShape>,
+    mut drawn: ResMut<WorldEntities>,
     time: Res<Time>,
     runner: Res<RunnerResource>,
+    mut selection: ResMut<Selection>,
 ) {
     egui::SidePanel::right("Control panel").show(egui_ctx.single_mut().get_mut(), |ui| {
         let id = ui.make_persistent_id("start/stop");
@@ -57,22 +104,67 @@ pub fn control_panel(
             })
             .body(|ui| {
                 ui.horizontal(|ui| {
+                    ui.label("Presets");
+                    for (name, num_creatures, dims) in PRESETS {
+                        if ui.button(name).clicked() {
+                            control_panel.experiment.num_creatures = num_creatures;
+                            control_panel.experiment.dims = dims;
+                        }
+                    }
+                    if ui.button("Window").clicked() {
+                        control_panel.experiment.dims = data.window_dims;
+                    }
+                });
+
+                let experiment = &mut control_panel.experiment;
+                egui::Grid::new("population").show(ui, |ui| {
                     ui.label("Number of creatures");
-                    ui.text_edit_singleline(&mut control_panel.initial_num_creatures);
+                    ui.add(
+                        egui::DragValue::new(&mut experiment.num_creatures)
+                            .clamp_range(0..=100_000),
+                    );
+                    ui.end_row();
+
+                    ui.label("World dimensions");
+                    ui.horizontal(|ui| {
+                        for dim in [&mut experiment.dims.0, &mut experiment.dims.1] {
+                            ui.add(
+                                egui::DragValue::new(dim)
+                                    .speed(1.0)
+                                    .clamp_range(0.0..=100_000.0),
+                            );
+                        }
+                    });
+                    ui.end_row();
                 });
 
                 ui.separator();
 
-                ui.vertical(|ui| {
-                    ui.label("World dimensions");
-                    ui.columns(2, |ui| {
-                        ui[0].text_edit_singleline(&mut control_panel.width);
-                        ui[1].text_edit_singleline(&mut control_panel.height);
+                let boundary = &mut control_panel.experiment.sim.boundary;
+                egui::ComboBox::from_label("Boundary")
+                    .selected_text(format!("{boundary:?}"))
+                    .show_ui(ui, |ui| {
+                        for b in Boundary::ALL {
+                            ui.selectable_value(boundary, b, format!("{b:?}"));
+                        }
                     });
+
+                ui.horizontal(|ui| {
+                    ui.label("Time step");
+                    ui.add(
+                        egui::DragValue::new(&mut control_panel.experiment.sim.dt)
+                            .speed(0.01)
+                            .clamp_range(0.01..=10.0),
+                    );
                 });
 
+                let errors = control_panel.experiment.errors();
+                for e in errors.iter() {
+                    ui.colored_label(egui::Color32::RED, e.to_string());
+                }
+
                 ui.horizontal(|ui| {
-                    ui.set_enabled(control_panel.can_create_sim);
+                    ui.set_enabled(control_panel.can_create_sim && errors.is_empty());
                     let button = ui.add_sized(
                         (ui.available_width(), 0.0),
                         egui::Button::new("Create new simulation"),
@@ -80,16 +172,13 @@ pub fn control_panel(
                     if button.clicked() {
                         runner
                             .tx
-                            .send(RunnerReq::Generate(Generate {
-                                num_creatures: control_panel.initial_num_creatures.parse().unwrap(),
-                                dims: (
-                                    control_panel.width.parse().unwrap(),
-                                    control_panel.height.parse().unwrap(),
-                                ),
-                                input_nodes: base_nodes.input_nodes.clone(),
-                                output_nodes: base_nodes.output_nodes.clone(),
-                            }))
+                            .send(RunnerReq::Generate(control_panel.experiment.clone()))
                             .expect("Could not send pause request");
+                        data.food_types = control_panel.experiment.sim.food.types.clone();
+                        data.species = control_panel.experiment.sim.species.clone();
+                        data.boundary = control_panel.experiment.sim.boundary;
+                        data.world = control_panel.experiment.sim.world.clone();
+                        data.world_dim = control_panel.experiment.dims;
                         control_panel.can_create_sim = false;
                         next_sim_state.set(SimulationState::Paused);
                         runner
@@ -131,6 +220,43 @@ pub fn control_panel(
                     }
                 });
 
+                ui.horizontal(|ui| {
+                    ui.set_enabled(
+                        !control_panel.can_create_sim
+                            && *sim_state.get() == SimulationState::Paused,
+                    );
+                    let button =
+                        ui.add_sized((ui.available_width(), 0.0), egui::Button::new("Step"));
+                    if button.clicked() {
+                        runner
+                            .tx
+                            .send(RunnerReq::Step)
+                            .expect("Could not send step request");
+                    }
+                });
+
+                ui.horizontal(|ui| {
+                    let old = (control_panel.target_tps, control_panel.unlimited_tps);
+                    ui.label("Ticks per second");
+                    ui.add_enabled(
+                        !control_panel.unlimited_tps,
+                        egui::DragValue::new(&mut control_panel.target_tps)
+                            .clamp_range(0.1..=10_000.0),
+                    );
+                    ui.checkbox(&mut control_panel.unlimited_tps, "Unlimited");
+                    if old != (control_panel.target_tps, control_panel.unlimited_tps) {
+                        let rate = if control_panel.unlimited_tps {
+                            TickRate::Unlimited
+                        } else {
+                            TickRate::Target(control_panel.target_tps)
+                        };
+                        runner
+                            .tx
+                            .send(RunnerReq::SetTickRate(rate))
+                            .expect("Could not send tick rate");
+                    }
+                });
+
                 match sim_state.get() {
                     SimulationState::None => {}
                     _ => {
@@ -140,8 +266,9 @@ pub fn control_panel(
                         );
 
                         if button.clicked() {
-                            clear_screen(&mut commands, rects);
+                            clear_screen(&mut commands, rects, &mut drawn);
                             data.creatures.clear();
+                            *selection = Selection::default();
                             control_panel.can_create_sim = true;
                             data.ticks = 0;
                             runner
@@ -155,9 +282,140 @@ pub fn control_panel(
                 }
             });
 
+        let id = ui.make_persistent_id("experiment");
+        CollapsingState::load_with_default_open(ui.ctx(), id, false)
+            .show_header(ui, |ui| {
+                ui.label("experiment");
+            })
+            .body(|ui| experiment_settings(ui, &mut control_panel));
+
+        let id = ui.make_persistent_id("world");
+        CollapsingState::load_with_default_open(ui.ctx(), id, false)
+            .show_header(ui, |ui| {
+                ui.label("world");
+            })
+            .body(|ui| {
+                ui.horizontal(|ui| {
+                    ui.text_edit_singleline(&mut control_panel.world_file);
+                    if ui.button("Load").clicked() {
+                        match WorldMap::load(&control_panel.world_file) {
+                            Ok(w) => {
+                                control_panel.experiment.sim.world = w;
+                                control_panel.world_error = None;
+                            }
+                            Err(e) => control_panel.world_error = Some(e.to_string()),
+                        }
+                    }
+                });
+                if let Some(e) = &control_panel.world_error {
+                    ui.colored_label(egui::Color32::RED, e);
+                }
+                let world = &control_panel.experiment.sim.world;
+                ui.label(format!(
+                    "{} obstacles, {} terrain zones",
+                    world.obstacles.len(),
+                    world.terrain.len()
+                ));
+                if ui.button("Clear").clicked() {
+                    control_panel.experiment.sim.world = WorldMap::default();
+                }
+            });
+
+        let id = ui.make_persistent_id("sensors");
+        CollapsingState::load_with_default_open(ui.ctx(), id, false)
+            .show_header(ui, |ui| {
+                ui.label("sensors");
+            })
+            .body(|ui| sensor_settings(ui, &mut control_panel.experiment.sim.sensors));
+
+        let id = ui.make_persistent_id("food");
+        CollapsingState::load_with_default_open(ui.ctx(), id, false)
+            .show_header(ui, |ui| {
+                ui.label("food");
+            })
+            .body(|ui| food_settings(ui, &mut control_panel.experiment.sim.food));
+
+        let id = ui.make_persistent_id("species");
+        CollapsingState::load_with_default_open(ui.ctx(), id, false)
+            .show_header(ui, |ui| {
+                ui.label("species");
+            })
+            .body(|ui| species_settings(ui, &mut control_panel.experiment.sim.species));
+
+        let id = ui.make_persistent_id("evolution");
+        CollapsingState::load_with_default_open(ui.ctx(), id, false)
+            .show_header(ui, |ui| {
+                ui.label("evolution");
+            })
+            .body(|ui| evolution_settings(ui, &mut control_panel.experiment.sim.evolution));
+
+        let id = ui.make_persistent_id("recording");
+        CollapsingState::load_with_default_open(ui.ctx(), id, false)
+            .show_header(ui, |ui| {
+                ui.label("recording");
+            })
+            .body(|ui| {
+                record_settings(ui, &mut control_panel.experiment.sim.record);
+                if let Some(status) = &data.recording {
+                    ui.label(status);
+                }
+            });
+
+        let id = ui.make_persistent_id("view");
+        CollapsingState::load_with_default_open(ui.ctx(), id, false)
+            .show_header(ui, |ui| {
+                ui.label("view");
+            })
+            .body(|ui| {
+                ui.label("Scroll to zoom, drag with the right mouse button to pan");
+                ui.checkbox(
+                    &mut control_panel.follow_selected,
+                    "Follow selected creature",
+                );
+                if ui.button("Reset view").clicked() {
+                    control_panel.reset_view = true;
+                    control_panel.follow_selected = false;
+                }
+            });
+
+        if let Some(tree) = selection.family_tree.clone() {
+            ui.separator();
+            let id = ui.make_persistent_id("family tree");
+            CollapsingState::load_with_default_open(ui.ctx(), id, true)
+                .show_header(ui, |ui| {
+                    ui.label(format!("Family of #{}", tree.root));
+                })
+                .body(|ui| {
+                    let mut highlight = selection.highlight_root;
+                    ui.label("Ancestors");
+                    lineage_row(ui, &tree, tree.root, true, &mut highlight);
+                    ui.label("Descendants");
+                    lineage_row(ui, &tree, tree.root, false, &mut highlight);
+
+                    if selection.highlight_root.is_some() && ui.button("Clear highlight").clicked()
+                    {
+                        highlight = None;
+                    }
+                    if highlight != selection.highlight_root {
+                        selection.highlight_root = highlight;
+                        selection.highlighted = match highlight {
+                            Some(id) => request_living_descendants(&runner, id),
+                            None => Default::default(),
+                        };
+                    }
+                });
+        }
+
         ui.separator();
         ui.label(format!("Ticks: {}", data.ticks));
+        let target = if control_panel.unlimited_tps {
+            "unlimited".to_owned()
+        } else {
+            format!("{:.1}", control_panel.target_tps)
+        };
+        ui.label(format!("TPS: {:.1} / {}", data.tps, target));
         ui.label(format!("FPS: {}", 1.0 / time.delta_seconds_f64()));
+        ui.checkbox(&mut control_panel.show_stats, "Statistics");
 
         ui.with_layout(Layout::bottom_up(egui::Align::Center), |ui| {
             let button = ui.add_sized((ui.available_width(), 0.0), egui::Button::new("Main menu"));
@@ -168,55 +426,761 @@ pub fn control_panel(
     });
 }
 
+fn sensor_settings(ui: &mut egui::Ui, sensors: &mut SensorConfig) {
+    egui::Grid::new("sensor settings").show(ui, |ui| {
+        ui.label("Rays");
+        ui.add(egui::DragValue::new(&mut sensors.rays).clamp_range(0..=32));
+        ui.end_row();
+
+        ui.label("Range");
+        ui.add(egui::DragValue::new(&mut sensors.range).clamp_range(0.0..=f32::MAX));
+        ui.end_row();
+
+        ui.label("Field of view");
+        ui.drag_angle(&mut sensors.fov);
+        ui.end_row();
+    });
+}
+
+fn species_settings(ui: &mut egui::Ui, species: &mut Vec<Species>) {
+    let mut remove = None;
+    for (idx, s) in species.iter_mut().enumerate() {
+        ui.horizontal(|ui| {
+            ui.add(egui::TextEdit::singleline(&mut s.name).desired_width(80.0));
+            ui.add(egui::TextEdit::singleline(&mut s.color).desired_width(60.0));
+            if ui.small_button("x").clicked() {
+                remove = Some(idx);
+            }
+        });
+        egui::Grid::new(("species settings", idx)).show(ui, |ui| {
+            ui.label("Share");
+            ui.add(
+                egui::DragValue::new(&mut s.share)
+                    .speed(0.01)
+                    .clamp_range(0.0..=f32::MAX),
+            );
+            ui.end_row();
+
+            ui.label("Diet");
+            egui::ComboBox::from_id_source(("diet", idx))
+                .selected_text(format!("{:?}", s.diet))
+                .show_ui(ui, |ui| {
+                    for d in Diet::ALL {
+                        ui.selectable_value(&mut s.diet, d, format!("{:?}", d));
+                    }
+                });
+            ui.end_row();
+
+            ui.label("Max speed");
+            ui.add(
+                egui::DragValue::new(&mut s.max_speed)
+                    .speed(0.01)
+                    .clamp_range(0.0..=f32::MAX),
+            );
+            ui.end_row();
+
+            ui.label("Turn rate");
+            ui.drag_angle(&mut s.turn_rate);
+            ui.end_row();
+
+            ui.label("Metabolism");
+            ui.add(
+                egui::DragValue::new(&mut s.metabolism)
+                    .speed(0.001)
+                    .clamp_range(0.0..=f32::MAX),
+            );
+            ui.end_row();
+
+            ui.label("Attack damage");
+            ui.add(
+                egui::DragValue::new(&mut s.attack_damage)
+                    .speed(0.1)
+                    .clamp_range(0.0..=f32::MAX),
+            );
+            ui.end_row();
+
+            ui.label("Meat nutrition");
+            ui.add(
+                egui::DragValue::new(&mut s.meat_nutrition)
+                    .speed(0.01)
+                    .clamp_range(0.0..=f32::MAX),
+            );
+            ui.end_row();
+        });
+        if s.template.is_some() {
+            ui.label("Uses a custom brain template");
+        }
+        ui.separator();
+    }
+    if let Some(idx) = remove {
+        species.remove(idx);
+    }
+    if ui.button("Add species").clicked() {
+        species.push(Species {
+            name: format!("species {}", species.len()),
+            ..Species::herbivore()
+        });
+    }
+}
+
+/// Loading replaces every setting in the panel, saving writes them all
+fn experiment_settings(ui: &mut egui::Ui, control_panel: &mut ControlPanel) {
+    ui.horizontal(|ui| {
+        ui.text_edit_singleline(&mut control_panel.experiment_file);
+        if ui.button("Load").clicked() {
+            control_panel.experiment_status = match Experiment::load(&control_panel.experiment_file)
+            {
+                Ok(e) => {
+                    control_panel.experiment = e;
+                    Some("Loaded".to_owned())
+                }
+                Err(e) => Some(e.to_string()),
+            };
+        }
+        if ui.button("Save").clicked() {
+            control_panel.experiment_status = match control_panel
+                .experiment
+                .save(&control_panel.experiment_file)
+            {
+                Ok(()) => Some("Saved".to_owned()),
+                Err(e) => Some(e.to_string()),
+            };
+        }
+    });
+    if let Some(status) = &control_panel.experiment_status {
+        ui.label(status);
+    }
+
+    let seed = &mut control_panel.experiment.seed;
+    ui.horizontal(|ui| {
+        let mut fixed = seed.is_some();
+        if ui.checkbox(&mut fixed, "Fixed seed").changed() {
+            *seed = fixed.then_some(0);
+        }
+        if let Some(seed) = seed {
+            ui.add(egui::DragValue::new(seed));
+        }
+    });
+}
+
+fn evolution_settings(ui: &mut egui::Ui, evolution: &mut EvolutionConfig) {
+    egui::Grid::new("evolution settings").show(ui, |ui| {
+        ui.label("Mate cooldown");
+        ui.add(egui::DragValue::new(&mut evolution.mate_cooldown).clamp_range(0..=100_000));
+        ui.end_row();
+
+        ui.label("Mate hunger cost");
+        ui.add(
+            egui::DragValue::new(&mut evolution.mate_hunger_cost)
+                .speed(0.01)
+                .clamp_range(0.0..=f32::MAX),
+        );
+        ui.end_row();
+
+        ui.label("Max hunger");
+        ui.add(
+            egui::DragValue::new(&mut evolution.max_hunger)
+                .speed(0.1)
+                .clamp_range(0.0..=f32::MAX),
+        );
+        ui.end_row();
+
+        ui.label("Starvation damage");
+        ui.add(
+            egui::DragValue::new(&mut evolution.starvation_damage)
+                .speed(0.01)
+                .clamp_range(0.0..=f32::MAX),
+        );
+        ui.end_row();
+
+        ui.label("Mutations per child");
+        ui.add(egui::DragValue::new(&mut evolution.mutations).clamp_range(1..=32));
+        ui.end_row();
+    });
+}
+
+fn record_settings(ui: &mut egui::Ui, record: &mut RecordConfig) {
+    ui.checkbox(&mut record.enabled, "Record new simulations");
+    ui.horizontal(|ui| {
+        ui.label("Directory");
+        ui.text_edit_singleline(&mut record.dir);
+    });
+    ui.horizontal(|ui| {
+        ui.label("Stats every");
+        ui.add(egui::DragValue::new(&mut record.stats_interval).clamp_range(1..=10_000));
+        ui.label("ticks");
+    });
+    ui.horizontal(|ui| {
+        ui.label("Creatures every");
+        ui.add(egui::DragValue::new(&mut record.creatures_interval).clamp_range(0..=10_000));
+        ui.label("ticks (0 = off)");
+    });
+    ui.checkbox(&mut record.events, "Births, deaths, meals and matings");
+}
+
+fn food_settings(ui: &mut egui::Ui, food: &mut FoodConfig) {
+    egui::Grid::new("food settings").show(ui, |ui| {
+        ui.label("Initial per creature");
+        ui.add(
+            egui::DragValue::new(&mut food.initial_per_creature)
+                .speed(0.01)
+                .clamp_range(0.0..=f32::MAX),
+        );
+        ui.end_row();
+
+        ui.label("Spawn rate");
+        ui.add(
+            egui::DragValue::new(&mut food.spawn_rate)
+                .speed(0.01)
+                .clamp_range(0.0..=f32::MAX),
+        );
+        ui.end_row();
+
+        ui.label("Max per 100x100");
+        ui.add(
+            egui::DragValue::new(&mut food.max_density)
+                .speed(0.1)
+                .clamp_range(0.0..=f32::MAX),
+        );
+        ui.end_row();
+
+        ui.label("Season length");
+        ui.add(egui::DragValue::new(&mut food.season_length).speed(10));
+        ui.end_row();
+
+        ui.label("Season amplitude");
+        ui.add(egui::Slider::new(&mut food.season_amplitude, 0.0..=1.0));
+        ui.end_row();
+
+        ui.label("Patches");
+        ui.add(egui::DragValue::new(&mut food.patches).clamp_range(0..=100));
+        ui.end_row();
+
+        ui.label("Patch radius");
+        ui.add(egui::DragValue::new(&mut food.patch_radius).clamp_range(1.0..=f32::MAX));
+        ui.end_row();
+    });
+
+    ui.separator();
+    ui.label("Food types (name, nutrition, weight, colour)");
+    let mut remove = None;
+    for (idx, t) in food.types.iter_mut().enumerate() {
+        ui.horizontal(|ui| {
+            ui.add(egui::TextEdit::singleline(&mut t.name).desired_width(60.0));
+            ui.add(egui::DragValue::new(&mut t.nutrition).speed(0.1));
+            ui.add(
+                egui::DragValue::new(&mut t.weight)
+                    .speed(0.1)
+                    .clamp_range(0.0..=f32::MAX),
+            );
+            ui.add(egui::TextEdit::singleline(&mut t.color).desired_width(60.0));
+            if ui.small_button("x").clicked() {
+                remove = Some(idx);
+            }
+        });
+    }
+    if let Some(idx) = remove {
+        food.types.remove(idx);
+    }
+    if ui.button("Add food type").clicked() {
+        food.types.push(FoodType {
+            name: format!("food {}", food.types.len()),
+            nutrition: 1.0,
+            weight: 1.0,
+            color: FOOD_COLOR.to_owned(),
+        });
+    }
+
+    ui.separator();
+    let field = &mut food.field;
+    ui.checkbox(&mut field.enabled, "Resource field");
+    ui.add_enabled_ui(field.enabled, |ui| {
+        egui::Grid::new("field settings").show(ui, |ui| {
+            ui.label("Cell size");
+            ui.add(egui::DragValue::new(&mut field.cell_size).clamp_range(1.0..=f32::MAX));
+            ui.end_row();
+
+            ui.label("Max");
+            ui.add(
+                egui::DragValue::new(&mut field.max)
+                    .speed(0.1)
+                    .clamp_range(0.01..=f32::MAX),
+            );
+            ui.end_row();
+
+            ui.label("Regrowth");
+            ui.add(
+                egui::DragValue::new(&mut field.regrowth)
+                    .speed(0.001)
+                    .clamp_range(0.0..=f32::MAX),
+            );
+            ui.end_row();
+
+            ui.label("Bite");
+            ui.add(
+                egui::DragValue::new(&mut field.bite)
+                    .speed(0.01)
+                    .clamp_range(0.0..=f32::MAX),
+            );
+            ui.end_row();
+
+            ui.label("Nutrition");
+            ui.add(egui::DragValue::new(&mut field.nutrition).speed(0.1));
+            ui.end_row();
+        });
+    });
+}
+
+/// One creature of the family tree, followed by its parents or children
+fn lineage_row(
+    ui: &mut egui::Ui,
+    tree: &FamilyTree,
+    id: usize,
+    ancestors: bool,
+    highlight: &mut Option<usize>,
+) {
+    let record = match tree.records.get(&id) {
+        Some(r) => r,
+        None => return,
+    };
+    let next = match (ancestors, record.parents) {
+        (true, Some((a, b))) => vec![a, b],
+        (true, None) => Vec::new(),
+        (false, _) => record.children.clone(),
+    };
+    let status = match record.death_tick {
+        Some(t) => format!("died {t}"),
+        None => "alive".to_owned(),
+    };
+    let text = format!(
+        "#{id} gen {}, born {}, {status}",
+        record.generation, record.birth_tick
+    );
+
+    let persistent_id = ui.make_persistent_id((id, ancestors));
+    CollapsingState::load_with_default_open(ui.ctx(), persistent_id, false)
+        .show_header(ui, |ui| {
+            ui.label(text);
+            if ui.small_button("highlight").clicked() {
+                *highlight = Some(id);
+            }
+        })
+        .body(|ui| {
+            for n in next.into_iter().filter(|n| tree.records.contains_key(n)) {
+                lineage_row(ui, tree, n, ancestors, highlight);
+            }
+        });
+}
+
+fn request_family_tree(runner: &RunnerResource, id: usize) -> Option<FamilyTree> {
+    match runner.request(|req| RunnerReq::GetFamilyTree(req, id)) {
+        RunnerRes::FamilyTree(t) => t,
+        other => {
+            warn!("Unexpected reply to a family tree request: {other:?}");
+            None
+        }
+    }
+}
+
+fn request_living_descendants(runner: &RunnerResource, id: usize) -> HashSet<usize> {
+    match runner.request(|req| RunnerReq::GetLivingDescendants(req, id)) {
+        RunnerRes::LivingDescendants(d) => d,
+        other => {
+            warn!("Unexpected reply to a descendants request: {other:?}");
+            HashSet::new()
+        }
+    }
+}
+
+/// Tells the runner which creature is selected and copies its published readout
+pub fn refresh_readout(
+    mut selection: ResMut<Selection>,
+    runner: Res<RunnerResource>,
+    mut sent: Local<Option<usize>>,
+) {
+    if selection.creature != *sent {
+        *sent = selection.creature;
+        runner
+            .tx
+            .send(RunnerReq::Select(selection.creature))
+            .expect("Could not send select request");
+    }
+
+    let snapshot = runner.snapshot.latest();
+    // the runner hasn't published for the new selection yet, don't show the old one meanwhile
+    if snapshot.selected != selection.creature {
+        let stale =
+            (selection.readout.as_ref()).is_some_and(|r| selection.creature != Some(r.creature.id));
+        if stale {
+            selection.bypass_change_detection().readout = None;
+        }
+        return;
+    }
+    selection.bypass_change_detection().readout = snapshot.readout.clone();
+}
+
+fn select(selection: &mut Selection, runner: &RunnerResource, id: usize) {
+    selection.creature = Some(id);
+    selection.family_tree = request_family_tree(runner, id);
+}
+
+/// Side panel of the selected creature with its live inputs and outputs
+pub fn selection_panel(
+    mut egui_ctx: Query<&mut EguiContext, With<PrimaryWindow>>,
+    mut selection: ResMut<Selection>,
+    mut control_panel: ResMut<ControlPanel>,
+    mut next_sim_state: ResMut<NextState<SimulationState>>,
+    data: Res<Simulation>,
+    runner: Res<RunnerResource>,
+) {
+    let Some(id) = selection.creature else {
+        return;
+    };
+
+    egui::SidePanel::left("Selection").show(egui_ctx.single_mut().get_mut(), |ui| {
+        ui.horizontal(|ui| {
+            ui.heading(format!("Creature #{id}"));
+            if ui.small_button("x").clicked() {
+                selection.creature = None;
+            }
+        });
+
+        match selection.readout.clone() {
+            Some(r) => {
+                let c = &r.creature;
+                let species = data.species.get(c.species).map_or("?", |s| &s.name);
+                ui.label(format!("{species}, generation {}", c.generation));
+                ui.label(format!("Age: {:.1}", r.age));
+                ui.label(format!("Health (energy): {:.2}", c.health));
+                ui.label(format!("Hunger: {:.2}", c.hunger));
+                ui.label(format!(
+                    "Position: {:.1}, {:.1}",
+                    c.position.0, c.position.1
+                ));
+
+                ui.horizontal_wrapped(|ui| {
+                    if ui.button("Pause on it").clicked() {
+                        next_sim_state.set(SimulationState::Paused);
+                        runner
+                            .tx
+                            .send(RunnerReq::Pause)
+                            .expect("Could not send pause request");
+                        control_panel.follow_selected = true;
+                    }
+                    if ui.button("Kill").clicked() {
+                        runner
+                            .tx
+                            .send(RunnerReq::Kill(id))
+                            .expect("Could not send kill request");
+                    }
+                    if ui.button("Clone").clicked() {
+                        runner
+                            .tx
+                            .send(RunnerReq::Clone(id))
+                            .expect("Could not send clone request");
+                    }
+                    let pinned = selection.favourites.contains(&id);
+                    if ui.button(if pinned { "Unpin" } else { "Pin" }).clicked() {
+                        if pinned {
+                            selection.favourites.retain(|f| *f != id);
+                        } else {
+                            selection.favourites.push(id);
+                        }
+                    }
+                });
+
+                ui.separator();
+                egui::ScrollArea::vertical()
+                    .max_height(ui.available_height() / 2.0)
+                    .show(ui, |ui| {
+                        egui::Grid::new("readout").striped(true).show(ui, |ui| {
+                            for (label, values) in [("Inputs", &r.inputs), ("Outputs", &r.outputs)]
+                            {
+                                ui.strong(label);
+                                ui.end_row();
+                                for (name, value) in values {
+                                    ui.label(name);
+                                    ui.label(format!("{value:.3}"));
+                                    ui.end_row();
+                                }
+                            }
+                        });
+                    });
+            }
+            None => {
+                ui.label("Died");
+            }
+        }
+
+        if !selection.favourites.is_empty() {
+            ui.separator();
+            ui.label("Pinned");
+            let mut clicked = None;
+            ui.horizontal_wrapped(|ui| {
+                for f in selection.favourites.iter() {
+                    let alive = data.creatures.iter().any(|c| c.id == *f);
+                    let text = if alive {
+                        format!("#{f}")
+                    } else {
+                        format!("#{f} (dead)")
+                    };
+                    if ui.selectable_label(*f == id, text).clicked() {
+                        clicked = Some(*f);
+                    }
+                }
+            });
+            if let Some(f) = clicked {
+                select(&mut selection, &runner, f);
+            }
+        }
+    });
+}
+
+/// Keeps the family tree and highlighted descendants up to date with births and deaths,
+/// asking the runner only after ticks that had any
+pub fn refresh_lineage(
+    mut selection: ResMut<Selection>,
+    runner: Res<RunnerResource>,
+    stats: Res<Stats>,
+    mut last_tick: Local<Option<usize>>,
+) {
+    let Some(latest) = stats.history.last() else {
+        return;
+    };
+    // ticks start over with a new simulation
+    let seen = last_tick.filter(|t| *t <= latest.tick);
+    let changed = stats
+        .history
+        .iter()
+        .rev()
+        .take_while(|s| seen.is_none_or(|t| s.tick > t))
+        .any(|s| s.births + s.deaths > 0);
+    *last_tick = Some(latest.tick);
+    if !changed {
+        return;
+    }
+
+    if let Some(id) = selection.creature {
+        selection.family_tree = request_family_tree(&runner, id);
+    }
+    if let Some(id) = selection.highlight_root {
+        selection.highlighted = request_living_descendants(&runner, id);
+    }
+}
+
+/// Keeps every tick's aggregates, starting over when a new simulation begins
+pub fn collect_stats(runner: Res<RunnerResource>, mut stats: ResMut<Stats>) {
+    for s in runner.stats.try_iter() {
+        if stats.history.last().is_some_and(|last| s.tick <= last.tick) {
+            stats.history.clear();
+        }
+        stats.history.push(s);
+    }
+}
+
+pub fn stats_window(
+    mut egui_ctx: Query<&mut EguiContext, With<PrimaryWindow>>,
+    stats: Res<Stats>,
+    mut control_panel: ResMut<ControlPanel>,
+) {
+    let history = &stats.history;
+    let series = |f: fn(&TickStats) -> f32| history.iter().map(f).collect::<Vec<_>>();
+    let (red, green, blue) = (
+        egui::Color32::from_rgb(0xeb, 0x40, 0x34),
+        egui::Color32::from_rgb(0x54, 0xff, 0x71),
+        egui::Color32::from_rgb(0x36, 0x86, 0xff),
+    );
+
+    let mut open = true;
+    egui::Window::new("Statistics")
+        .open(&mut open)
+        .show(egui_ctx.single_mut().get_mut(), |ui| {
+            ui.horizontal(|ui| {
+                ui.text_edit_singleline(&mut control_panel.stats_file);
+                if ui.button("Export CSV").clicked() {
+                    control_panel.stats_status =
+                        Some(match write_csv(&control_panel.stats_file, history) {
+                            Ok(()) => format!("Wrote {} ticks", history.len()),
+                            Err(e) => e.to_string(),
+                        });
+                }
+            });
+            if let Some(status) = &control_panel.stats_status {
+                ui.label(status);
+            }
+
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                chart(
+                    ui,
+                    "Population",
+                    &[
+                        ("alive", blue, series(|s| s.population as f32)),
+                        ("births", green, series(|s| s.births as f32)),
+                        ("deaths", red, series(|s| s.deaths as f32)),
+                    ],
+                );
+                chart(ui, "Food", &[("food", green, series(|s| s.food as f32))]);
+                chart(
+                    ui,
+                    "Energy (health)",
+                    &[
+                        ("mean", blue, series(|s| s.mean_health)),
+                        ("max", green, series(|s| s.max_health)),
+                    ],
+                );
+                chart(ui, "Hunger", &[("mean", red, series(|s| s.mean_hunger))]);
+                chart(
+                    ui,
+                    "Brain size",
+                    &[
+                        ("nodes", blue, series(|s| s.mean_nodes)),
+                        ("edges", green, series(|s| s.mean_edges)),
+                    ],
+                );
+                chart(
+                    ui,
+                    "Species alive",
+                    &[("species", blue, series(|s| s.species as f32))],
+                );
+            });
+        });
+    control_panel.show_stats = open;
+}
+
+/// Line chart of whole series, thinned out to about one point per pixel
+fn chart(ui: &mut egui::Ui, title: &str, series: &[(&str, egui::Color32, Vec<f32>)]) {
+    ui.horizontal(|ui| {
+        ui.label(title);
+        for (name, color, values) in series {
+            let last = values.last().copied().unwrap_or(0.0);
+            ui.colored_label(*color, format!("{name}: {last:.2}"));
+        }
+    });
+
+    let (response, painter) = ui.allocate_painter(
+        egui::vec2(ui.available_width(), CHART_HEIGHT),
+        egui::Sense::hover(),
+    );
+    let rect = response.rect;
+    painter.rect_stroke(rect, 0.0, egui::Stroke::new(1.0, egui::Color32::GRAY));
+
+    let values = series.iter().flat_map(|s| s.2.iter().copied());
+    let min = values.clone().fold(f32::INFINITY, f32::min);
+    let max = values.fold(f32::NEG_INFINITY, f32::max);
+    if !min.is_finite() || !max.is_finite() {
+        return;
+    }
+    let range = if max > min { max - min } else { 1.0 };
+
+    for (_, color, values) in series {
+        if values.len() < 2 {
+            continue;
+        }
+        let step = (values.len() as f32 / rect.width()).max(1.0);
+        let points = (0..)
+            .map(|i| (i as f32 * step) as usize)
+            .take_while(|i| *i < values.len())
+            .chain([values.len() - 1])
+            .map(|i| {
+                egui::pos2(
+                    rect.left() + rect.width() * i as f32 / (values.len() - 1) as f32,
+                    rect.bottom() - rect.height() * (values[i] - min) / range,
+                )
+            })
+            .collect::<Vec<_>>();
+        painter.add(egui::Shape::line(points, egui::Stroke::new(1.5, *color)));
+    }
+
+    let font = egui::FontId::monospace(10.0);
+    painter.text(
+        rect.left_top(),
+        egui::Align2::LEFT_TOP,
+        format!("{max:.2}"),
+        font.clone(),
+        egui::Color32::GRAY,
+    );
+    painter.text(
+        rect.left_bottom(),
+        egui::Align2::LEFT_BOTTOM,
+        format!("{min:.2}"),
+        font,
+        egui::Color32::GRAY,
+    );
+}
+
 pub fn initialize_world(
     mut shapes: ShapeCommands,
+    mut commands: Commands,
     mut data: ResMut<Simulation>,
     runner: Res<RunnerResource>,
+    selection: Res<Selection>,
+    mut drawn: ResMut<WorldEntities>,
+    mut creature_shapes: CreatureShapes,
+    mut food_shapes: FoodShapes,
 ) {
-    runner
-        .tx
-        .send(RunnerReq::GetPositions)
-        .expect("Could not send positions request");
-    match runner.rx.recv().expect("Could not receive positions") {
-        RunnerRes::Positions(p) => {
-            data.creatures = p.creatures;
-            data.food = p.food;
-        }
-        _ => unreachable!(),
-    }
+    read_snapshot(&mut data, &runner);
 
     render_world(&mut data, &mut shapes);
+    sync_creatures(
+        &data,
+        &selection,
+        &shapes,
+        &mut commands,
+        &mut drawn,
+        &mut creature_shapes,
+    );
+    sync_food(&data, &shapes, &mut commands, &mut drawn, &mut food_shapes);
+}
+
+/// Copies the runner's latest published state, never waits for a tick
+fn read_snapshot(data: &mut Simulation, runner: &RunnerResource) {
+    let snapshot = runner.snapshot.latest();
+    data.creatures = snapshot.creatures.clone();
+    data.food = snapshot.food.clone();
+    data.ticks = snapshot.ticks;
+    data.tps = snapshot.tps;
+    data.recording = snapshot.recording.clone();
 }
 
-fn clear_screen(commands: &mut Commands, rects: Query<Entity, With<RectangleComponent>>) {
+fn clear_screen(
+    commands: &mut Commands,
+    rects: Query<Entity, WorldShape>,
+    drawn: &mut WorldEntities,
+) {
     for item in rects.iter() {
         commands.entity(item).despawn_recursive();
     }
+    *drawn = WorldEntities::default();
 }
 
+/// Moves the creature and food shapes to the latest snapshot, the world itself is only
+/// drawn once when the simulation is created
 pub fn run_simulation(
     mut data: ResMut<Simulation>,
-    mut shapes: ShapeCommands,
+    shapes: ShapeCommands,
     mut commands: Commands,
-    rects: Query<Entity, With<RectangleComponent>>,
     runner: Res<RunnerResource>,
+    selection: Res<Selection>,
+    mut drawn: ResMut<WorldEntities>,
+    mut creature_shapes: CreatureShapes,
+    mut food_shapes: FoodShapes,
 ) {
-    clear_screen(&mut commands, rects);
-
-    runner
-        .tx
-        .send(RunnerReq::GetPositions)
-        .expect("Could not send positions request");
-    match runner.rx.recv().expect("Could not receive positions") {
-        RunnerRes::Positions(p) => {
-            data.creatures = p.creatures;
-            data.food = p.food;
-        }
-        _ => unreachable!(),
+    let (ticks, count) = (data.ticks, data.creatures.len());
+    read_snapshot(&mut data, &runner);
+    // selection colors, kills and clones can change without a tick
+    if ticks == data.ticks && count == data.creatures.len() && !selection.is_changed() {
+        return;
     }
 
-    render_world(&mut data, &mut shapes);
-    data.ticks += 1;
+    sync_creatures(
+        &data,
+        &selection,
+        &shapes,
+        &mut commands,
+        &mut drawn,
+        &mut creature_shapes,
+    );
+    sync_food(&data, &shapes, &mut commands, &mut drawn, &mut food_shapes);
 }
 
 fn convert_bottom_left_to_center_coords(pos: Vec2, dims: (f32, f32)) -> Vec2 {
@@ -225,65 +1189,348 @@ fn convert_bottom_left_to_center_coords(pos: Vec2, dims: (f32, f32)) -> Vec2 {
     Vec2::new(pos.x - half_width, half_height - pos.y)
 }
 
+fn convert_center_to_bottom_left_coords(pos: Vec2, dims: (f32, f32)) -> Vec2 {
+    let half_width = dims.0 / 2.0;
+    let half_height = dims.1 / 2.0;
+    Vec2::new(pos.x + half_width, half_height - pos.y)
+}
+
+/// Scroll zooms around the cursor, dragging with the right mouse button pans, which also
+/// stops following the selected creature
+pub fn world_camera(
+    mut camera: Query<(&mut Transform, &mut OrthographicProjection), With<WorldCamera>>,
+    window: Query<(Entity, &Window), With<PrimaryWindow>>,
+    mut egui_ctx: Query<&mut EguiContext, With<PrimaryWindow>>,
+    mut wheel: EventReader<MouseWheel>,
+    mut motion: EventReader<MouseMotion>,
+    buttons: Res<ButtonInput<MouseButton>>,
+    mut control_panel: ResMut<ControlPanel>,
+    data: Res<Simulation>,
+    selection: Res<Selection>,
+) {
+    let (entity, w) = window.single();
+    let (mut transform, mut projection) = camera.single_mut();
+
+    if control_panel.reset_view {
+        control_panel.reset_view = false;
+        projection.scale = 1.0;
+        transform.translation = Vec3::new(0.0, 0.0, transform.translation.z);
+    }
+
+    let cursor = w.cursor_position();
+    let over_egui = egui_ctx.single_mut().get_mut().wants_pointer_input();
+    for event in wheel.read() {
+        if event.window != entity || over_egui {
+            continue;
+        }
+        let factor = if event.y > 0.0 { 0.9 } else { 1.1 };
+        let scale = (projection.scale * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+        // keep the point under the cursor in place
+        if let Some(cursor) = cursor {
+            let offset = Vec2::new(cursor.x - w.width() / 2.0, w.height() / 2.0 - cursor.y);
+            let shift = offset * (projection.scale - scale);
+            transform.translation += shift.extend(0.0);
+        }
+        projection.scale = scale;
+    }
+
+    let delta = motion.read().map(|m| m.delta).sum::<Vec2>();
+    if cursor.is_some() && !over_egui && buttons.pressed(MouseButton::Right) && delta != Vec2::ZERO
+    {
+        transform.translation.x -= delta.x * projection.scale;
+        transform.translation.y += delta.y * projection.scale;
+        control_panel.follow_selected = false;
+    }
+
+    if !control_panel.follow_selected {
+        return;
+    }
+    let followed = selection
+        .creature
+        .and_then(|id| data.creatures.iter().find(|c| c.id == id));
+    if let Some(c) = followed {
+        let coords = convert_bottom_left_to_center_coords(
+            Vec2::new(c.position.0, c.position.1),
+            data.window_dims,
+        );
+        transform.translation = coords.extend(transform.translation.z);
+    }
+}
+
 // (c.position.0 + 5.0 >= position.x && c.position.0 - 5.0 <= position.x)
 //                     && (c.position.1 + 5.0 >= position.y && c.position.1 - 5.0 <= position.y)
 
 pub fn inspect_creature(
     buttons: Res<ButtonInput<MouseButton>>,
     q_windows: Query<&Window, With<PrimaryWindow>>,
+    camera: Query<(&Camera, &GlobalTransform), With<WorldCamera>>,
+    mut egui_ctx: Query<&mut EguiContext, With<PrimaryWindow>>,
     data: Res<Simulation>,
     mut inspect_net: EventWriter<InspectNet>,
     runner: Res<RunnerResource>,
+    inspect_state: Res<State<InspectWindowState>>,
+    mut selection: ResMut<Selection>,
 ) {
-    if buttons.just_pressed(MouseButton::Left) {
-        let window = q_windows.single();
-        if let Some(position) = window.cursor_position() {
-            // let position =
-            //     convert_bottom_left_to_center_coords(position, (window.width(), window.height()));
+    if buttons.just_pressed(MouseButton::Left)
+        && !egui_ctx.single_mut().get_mut().is_pointer_over_area()
+    {
+        let (camera, camera_transform) = camera.single();
+        let position = q_windows
+            .single()
+            .cursor_position()
+            .and_then(|p| camera.viewport_to_world_2d(camera_transform, p))
+            .map(|p| convert_center_to_bottom_left_coords(p, data.window_dims));
+        if let Some(position) = position {
             if let Some(c) = data.creatures.iter().find(|c| {
                 (c.position.0 + 5.0 >= position.x && c.position.0 - 5.0 <= position.x)
                     && (c.position.1 + 5.0 >= position.y && c.position.1 - 5.0 <= position.y)
             }) {
-                runner
-                    .tx
-                    .send(RunnerReq::GetNet(c.id))
-                    .expect("Could not send net request");
-                match runner.rx.recv().expect("Could not receive net") {
-                    RunnerRes::Net(n) => inspect_net.send(InspectNet(n.unwrap())),
-                    _ => unreachable!(),
-                };
+                select(&mut selection, &runner, c.id);
+
+                if *inspect_state.get() == InspectWindowState::Display {
+                    match runner.request(|req| RunnerReq::GetNet(req, c.id)) {
+                        RunnerRes::Net(Some(n)) => {
+                            inspect_net.send(InspectNet(n, Some(c.id)));
+                        }
+                        RunnerRes::Net(None) => {}
+                        other => warn!("Unexpected reply to a net request: {other:?}"),
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Streams the selected creature's published brain to the inspector whenever the world
+/// moved on
+pub fn follow_inspected(
+    selection: Res<Selection>,
+    runner: Res<RunnerResource>,
+    editor: Res<Editor>,
+    mut inspect_net: EventWriter<InspectNet>,
+    mut last: Local<Option<(usize, usize)>>,
+) {
+    let Some(id) = selection.creature else {
+        return;
+    };
+    // forgotten while editing so the live brain comes back once editing stops
+    if editor.active {
+        *last = None;
+        return;
+    }
+    let snapshot = runner.snapshot.latest();
+    // the runner hasn't published for the new selection yet
+    if snapshot.selected != Some(id) || *last == Some((id, snapshot.ticks)) {
+        return;
+    }
+    *last = Some((id, snapshot.ticks));
+
+    if let Some(n) = &snapshot.brain {
+        inspect_net.send(InspectNet(n.clone(), Some(id)));
+    }
+}
+
+/// Puts brains edited in the inspector back into their creatures
+pub fn apply_edited_net(
+    mut events: EventReader<ApplyNet>,
+    runner: Res<RunnerResource>,
+    mut editor: ResMut<Editor>,
+) {
+    for e in events.read() {
+        let net = e.net.clone();
+        editor.status = match runner.request(|req| RunnerReq::SetNet(req, e.creature, net)) {
+            RunnerRes::NetSet(Ok(())) => Some(format!("Applied to #{}", e.creature)),
+            RunnerRes::NetSet(Err(err)) => Some(err.to_string()),
+            other => {
+                warn!("Unexpected reply to a set net request: {other:?}");
+                continue;
+            }
+        };
+    }
+}
+
+fn draw_shape(shapes: &mut ShapeCommands, shape: &Shape, window_dims: (f32, f32), z: f32) {
+    let convert =
+        |p: (f32, f32)| convert_bottom_left_to_center_coords(Vec2::new(p.0, p.1), window_dims);
+    match shape {
+        Shape::Rect { min, max } => {
+            let center = convert(((min.0 + max.0) / 2.0, (min.1 + max.1) / 2.0));
+            shapes.transform.translation = center.extend(z);
+            shapes.rect(Vec2::new(max.0 - min.0, max.1 - min.1));
+        }
+        Shape::Circle { center, radius } => {
+            shapes.transform.translation = convert(*center).extend(z);
+            shapes.circle(*radius);
+        }
+        Shape::Polygon { points } => {
+            // triangle fan, fine for the convex polygons world files use
+            shapes.transform.translation = Vec3::new(0.0, 0.0, z);
+            for w in points.windows(2).skip(1) {
+                shapes.triangle(convert(points[0]), convert(w[0]), convert(w[1]));
             }
         }
     }
 }
 
 fn render_world(data: &mut Simulation, shapes: &mut ShapeCommands) {
-    shapes.color = Color::hex(CREATURE_COLOR).unwrap();
-    data.creatures.iter().for_each(|c| {
-        // if speed > 0.0 && (movement_vec.x > 0.0 || movement_vec.y > 0.0) {
-        //     // inspect_net.send(InspectNet(c.brain.clone()));
-        //     // next_sim_state.set(SimulationState::Paused);
-        //     shapes.color = Color::hex("eb4034").unwrap();
-        //     shapes.transform.translation = Vec3::new(c.position.0, c.position.1, -1.0);
-        //     shapes.circle(15.0);
-
-        //     shapes.color = Color::hex("ffffff").unwrap();
-        //     shapes.transform.translation = Vec3::new(c.position.0, c.position.1, -0.5);
-        //     shapes.circle(10.0);
-        // }
+    let center = convert_bottom_left_to_center_coords(
+        Vec2::new(data.world_dim.0 / 2.0, data.world_dim.1 / 2.0),
+        data.window_dims,
+    );
+    shapes.transform = Transform::from_xyz(center.x, center.y, -1.0);
+    shapes.color = Color::hex(match data.boundary {
+        Boundary::Clamp => "808080",
+        Boundary::Wrap => "d0d0d0",
+        Boundary::Reflect => "3686ff",
+        Boundary::Lethal => "eb4034",
+    })
+    .unwrap();
+    shapes.hollow = true;
+    shapes.thickness = 2.0;
+    shapes.rect(Vec2::new(data.world_dim.0, data.world_dim.1));
+    shapes.hollow = false;
+
+    for t in data.world.terrain.iter() {
+        shapes.color = Color::hex(match t.kind {
+            TerrainKind::Slow { .. } => "e8dcb0",
+            TerrainKind::Damaging { .. } => "ffc0c0",
+            TerrainKind::Fertile { .. } => "c8f5d0",
+        })
+        .unwrap();
+        draw_shape(shapes, &t.shape, data.window_dims, -3.0);
+    }
+    shapes.color = Color::hex(OBSTACLE_COLOR).unwrap();
+    for o in data.world.obstacles.iter() {
+        draw_shape(shapes, o, data.window_dims, -2.0);
+    }
+}
+
+fn creature_color(data: &Simulation, selection: &Selection, c: &BasicCreature) -> Color {
+    if selection.creature == Some(c.id) {
+        Color::hex(SELECTED_COLOR).unwrap()
+    } else if selection.highlighted.contains(&c.id) {
+        Color::hex(DESCENDANT_COLOR).unwrap()
+    } else {
+        data.species
+            .get(c.species)
+            .and_then(|s| Color::hex(&s.color).ok())
+            .unwrap_or(Color::hex(CREATURE_COLOR).unwrap())
+    }
+}
+
+/// Spawns a shape for every new creature, moves the ones already drawn and despawns the dead
+fn sync_creatures(
+    data: &Simulation,
+    selection: &Selection,
+    shapes: &ShapeCommands,
+    commands: &mut Commands,
+    drawn: &mut WorldEntities,
+    query: &mut CreatureShapes,
+) {
+    let mut alive = HashMap::with_capacity(data.creatures.len());
+    for c in data.creatures.iter() {
+        let color = creature_color(data, selection, c);
+        // creatures crossing a wrapping edge show up on the other side too
+        for copy in data
+            .boundary
+            .seam_copies(c.position, data.world_dim, CREATURE_DIM)
+        {
+            let coords = convert_bottom_left_to_center_coords(
+                Vec2::new(
+                    c.position.0 + copy.0 as f32 * data.world_dim.0,
+                    c.position.1 + copy.1 as f32 * data.world_dim.1,
+                ),
+                data.window_dims,
+            );
+            // screen y points the other way to world y
+            let transform = Transform::from_translation(coords.extend(0.0))
+                .with_rotation(Quat::from_rotation_z(-c.heading));
+
+            let key = (c.id, copy);
+            let entity = match drawn.creatures.remove(&key).filter(|e| query.contains(*e)) {
+                Some(entity) => {
+                    let (mut t, mut fill) = query.get_mut(entity).unwrap();
+                    *t = transform;
+                    fill.color = color;
+                    entity
+                }
+                None => {
+                    let mut config = shapes.config().clone();
+                    config.transform = transform;
+                    config.color = color;
+                    let forward = Vec2::new(CREATURE_DIM, 0.0);
+                    let side = forward.perp() / 2.0;
+                    commands
+                        .spawn((
+                            ShapeBundle::triangle(
+                                &config,
+                                forward,
+                                -forward / 2.0 + side,
+                                -forward / 2.0 - side,
+                            ),
+                            CreatureShape,
+                        ))
+                        .id()
+                }
+            };
+            alive.insert(key, entity);
+        }
+    }
+
+    for (_, entity) in drawn.creatures.drain() {
+        if query.contains(entity) {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+    drawn.creatures = alive;
+}
 
+fn sync_food(
+    data: &Simulation,
+    shapes: &ShapeCommands,
+    commands: &mut Commands,
+    drawn: &mut WorldEntities,
+    query: &mut FoodShapes,
+) {
+    let mut present = HashMap::with_capacity(data.food.len());
+    for f in data.food.iter() {
         let coords = convert_bottom_left_to_center_coords(
-            Vec2::new(c.position.0, c.position.1),
+            Vec2::new(f.position.0, f.position.1),
             data.window_dims,
         );
-        shapes.transform.translation = Vec3::new(coords.x, coords.y, 0.0);
-        shapes.rect(Vec2::new(CREATURE_DIM, CREATURE_DIM));
-    });
+        let transform = Transform::from_translation(coords.extend(0.0));
+        let color = data
+            .food_types
+            .get(f.kind)
+            .and_then(|t| Color::hex(&t.color).ok())
+            .unwrap_or(Color::hex(FOOD_COLOR).unwrap());
 
-    shapes.color = Color::hex(FOOD_COLOR).unwrap();
-    data.food.iter().for_each(|c| {
-        let coords = convert_bottom_left_to_center_coords(Vec2::new(c.0, c.1), data.window_dims);
-        shapes.transform.translation = Vec3::new(coords.x, coords.y, 0.0);
-        shapes.rect(Vec2::new(CREATURE_DIM, CREATURE_DIM));
-    });
+        let entity = match drawn.food.remove(&f.id).filter(|e| query.contains(*e)) {
+            Some(entity) => {
+                let (mut t, mut fill) = query.get_mut(entity).unwrap();
+                *t = transform;
+                fill.color = color;
+                entity
+            }
+            None => {
+                let mut config = shapes.config().clone();
+                config.transform = transform;
+                config.color = color;
+                commands
+                    .spawn((
+                        ShapeBundle::rect(&config, Vec2::new(CREATURE_DIM, CREATURE_DIM)),
+                        FoodShape,
+                    ))
+                    .id()
+            }
+        };
+        present.insert(f.id, entity);
+    }
+
+    for (_, entity) in drawn.food.drain() {
+        if query.contains(entity) {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+    drawn.food = present;
 }
@@ -0,0 +1,453 @@
+use std::{f32::consts::PI, fs, path::Path};
+
+use bevy::math::Vec2;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::sensors::ray_circle;
+
+/// What happens to creatures reaching the edge of the world
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub enum Boundary {
+    #[default]
+    Clamp,
+    /// Leaving one side enters from the opposite one
+    Wrap,
+    /// Bounces off the wall, mirroring the heading
+    Reflect,
+    /// Touching a wall kills
+    Lethal,
+}
+
+impl Boundary {
+    pub const ALL: [Boundary; 4] = [
+        Boundary::Clamp,
+        Boundary::Wrap,
+        Boundary::Reflect,
+        Boundary::Lethal,
+    ];
+
+    /// Whether sensors can see the walls
+    pub fn has_walls(self) -> bool {
+        self != Boundary::Wrap
+    }
+
+    /// Shortest offset from `a` to `b`, crossing the edges when wrapping
+    pub fn delta(self, a: (f32, f32), b: (f32, f32), world_dim: (f32, f32)) -> Vec2 {
+        let mut d = Vec2::new(b.0 - a.0, b.1 - a.1);
+        if self == Boundary::Wrap {
+            if d.x.abs() > world_dim.0 / 2.0 {
+                d.x -= world_dim.0 * d.x.signum();
+            }
+            if d.y.abs() > world_dim.1 / 2.0 {
+                d.y -= world_dim.1 * d.y.signum();
+            }
+        }
+        d
+    }
+
+    /// Where something within `reach` of an edge is also seen, as multiples of the world
+    /// size to shift it by. Only wrapping worlds show it across the edges it touches, the
+    /// first copy is always the unshifted one
+    pub fn seam_copies(
+        self,
+        position: (f32, f32),
+        world_dim: (f32, f32),
+        reach: f32,
+    ) -> Vec<(i8, i8)> {
+        if self != Boundary::Wrap {
+            return vec![(0, 0)];
+        }
+        let axis = |p: f32, dim: f32| {
+            let mut shifts = vec![0];
+            if p < reach {
+                shifts.push(1);
+            }
+            if p > dim - reach {
+                shifts.push(-1);
+            }
+            shifts
+        };
+        let ys = axis(position.1, world_dim.1);
+        axis(position.0, world_dim.0)
+            .into_iter()
+            .flat_map(|x| ys.iter().map(move |y| (x, *y)))
+            .collect()
+    }
+
+    /// Moves a creature back into the world, returns false if the wall killed it
+    pub fn apply(
+        self,
+        position: &mut (f32, f32),
+        heading: &mut f32,
+        world_dim: (f32, f32),
+    ) -> bool {
+        let out_x = position.0 < 0.0 || position.0 > world_dim.0;
+        let out_y = position.1 < 0.0 || position.1 > world_dim.1;
+        match self {
+            Boundary::Wrap => {
+                position.0 = position.0.rem_euclid(world_dim.0.max(f32::EPSILON));
+                position.1 = position.1.rem_euclid(world_dim.1.max(f32::EPSILON));
+                return true;
+            }
+            Boundary::Reflect => {
+                if out_x {
+                    *heading = PI - *heading;
+                }
+                if out_y {
+                    *heading = -*heading;
+                }
+            }
+            Boundary::Clamp | Boundary::Lethal => {}
+        }
+
+        position.0 = position.0.clamp(0.0, world_dim.0);
+        position.1 = position.1.clamp(0.0, world_dim.1);
+        !(self == Boundary::Lethal && (out_x || out_y))
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum WorldError {
+    #[error("Could not read world file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Could not parse world file: {0}")]
+    Parse(#[from] ron::error::SpannedError),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum Shape {
+    Rect {
+        min: (f32, f32),
+        max: (f32, f32),
+    },
+    Circle {
+        center: (f32, f32),
+        radius: f32,
+    },
+    /// Convex polygon, points in order around the edge
+    Polygon {
+        points: Vec<(f32, f32)>,
+    },
+}
+
+impl Shape {
+    pub fn contains(&self, p: (f32, f32)) -> bool {
+        match self {
+            Shape::Rect { min, max } => {
+                p.0 >= min.0 && p.0 <= max.0 && p.1 >= min.1 && p.1 <= max.1
+            }
+            Shape::Circle { center, radius } => {
+                Vec2::new(p.0 - center.0, p.1 - center.1).length_squared() <= radius * radius
+            }
+            Shape::Polygon { points } => {
+                // even-odd rule
+                let mut inside = false;
+                for (i, a) in points.iter().enumerate() {
+                    let b = points[(i + 1) % points.len()];
+                    if (a.1 > p.1) != (b.1 > p.1)
+                        && p.0 < (b.0 - a.0) * (p.1 - a.1) / (b.1 - a.1) + a.0
+                    {
+                        inside = !inside;
+                    }
+                }
+                inside
+            }
+        }
+    }
+
+    /// Distance along `dir` to the edge of the shape, `dir` must be normalized
+    pub fn ray(&self, origin: Vec2, dir: Vec2) -> Option<f32> {
+        match self {
+            Shape::Rect { min, max } => {
+                let mut near = f32::NEG_INFINITY;
+                let mut far = f32::INFINITY;
+                for (o, d, lo, hi) in [
+                    (origin.x, dir.x, min.0, max.0),
+                    (origin.y, dir.y, min.1, max.1),
+                ] {
+                    if d == 0.0 {
+                        if o < lo || o > hi {
+                            return None;
+                        }
+                        continue;
+                    }
+                    let (t1, t2) = ((lo - o) / d, (hi - o) / d);
+                    near = near.max(t1.min(t2));
+                    far = far.min(t1.max(t2));
+                }
+                (near <= far && far >= 0.0).then_some(near.max(0.0))
+            }
+            Shape::Circle { center, radius } => {
+                ray_circle(origin, dir, Vec2::new(center.0, center.1), *radius)
+            }
+            Shape::Polygon { points } => {
+                if self.contains((origin.x, origin.y)) {
+                    return Some(0.0);
+                }
+                points
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(i, a)| {
+                        let a = Vec2::new(a.0, a.1);
+                        let b = points[(i + 1) % points.len()];
+                        let edge = Vec2::new(b.0, b.1) - a;
+                        let denom = dir.perp_dot(edge);
+                        if denom == 0.0 {
+                            return None;
+                        }
+                        let t = (a - origin).perp_dot(edge) / denom;
+                        let u = (a - origin).perp_dot(dir) / denom;
+                        (t >= 0.0 && (0.0..=1.0).contains(&u)).then_some(t)
+                    })
+                    .min_by(|a, b| a.total_cmp(b))
+            }
+        }
+    }
+
+    /// Smallest rectangle containing the shape
+    pub fn bounds(&self) -> ((f32, f32), (f32, f32)) {
+        match self {
+            Shape::Rect { min, max } => (*min, *max),
+            Shape::Circle { center, radius } => (
+                (center.0 - radius, center.1 - radius),
+                (center.0 + radius, center.1 + radius),
+            ),
+            Shape::Polygon { points } => points.iter().fold(
+                (
+                    (f32::INFINITY, f32::INFINITY),
+                    (f32::NEG_INFINITY, f32::NEG_INFINITY),
+                ),
+                |(min, max), p| {
+                    (
+                        (min.0.min(p.0), min.1.min(p.1)),
+                        (max.0.max(p.0), max.1.max(p.1)),
+                    )
+                },
+            ),
+        }
+    }
+
+    /// Random point inside the shape, None if sampling kept missing it
+    pub fn sample(&self, rng: &mut impl Rng) -> Option<(f32, f32)> {
+        let (min, max) = self.bounds();
+        if !(min.0 <= max.0 && min.1 <= max.1) {
+            return None;
+        }
+        (0..20)
+            .map(|_| (rng.gen_range(min.0..=max.0), rng.gen_range(min.1..=max.1)))
+            .find(|p| self.contains(*p))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum TerrainKind {
+    /// Multiplies the speed of creatures inside
+    Slow { factor: f32 },
+    /// Health lost per tick inside
+    Damaging { damage: f32 },
+    /// Extra food items spawned inside per tick
+    Fertile { spawn_rate: f32 },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Terrain {
+    pub shape: Shape,
+    pub kind: TerrainKind,
+}
+
+/// Static layout of a world, loaded from a RON file
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct WorldMap {
+    pub obstacles: Vec<Shape>,
+    pub terrain: Vec<Terrain>,
+}
+
+impl WorldMap {
+    pub fn load(path: impl AsRef<Path>) -> Result<WorldMap, WorldError> {
+        Ok(ron::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    pub fn blocked(&self, p: (f32, f32)) -> bool {
+        self.obstacles.iter().any(|o| o.contains(p))
+    }
+
+    /// Nearest obstacle along the ray
+    pub fn ray(&self, origin: Vec2, dir: Vec2) -> Option<f32> {
+        self.obstacles
+            .iter()
+            .filter_map(|o| o.ray(origin, dir))
+            .min_by(|a, b| a.total_cmp(b))
+    }
+
+    /// Speed multiplier and damage per tick at `p`
+    pub fn terrain_effects(&self, p: (f32, f32)) -> (f32, f32) {
+        self.terrain.iter().filter(|t| t.shape.contains(p)).fold(
+            (1.0, 0.0),
+            |(speed, damage), t| match t.kind {
+                TerrainKind::Slow { factor } => (speed * factor, damage),
+                TerrainKind::Damaging { damage: d } => (speed, damage + d),
+                TerrainKind::Fertile { .. } => (speed, damage),
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::f32::consts::PI;
+
+    use bevy::math::Vec2;
+
+    use super::{Boundary, Shape, WorldMap};
+
+    const DIM: (f32, f32) = (100.0, 50.0);
+
+    fn apply(
+        boundary: Boundary,
+        mut position: (f32, f32),
+        mut heading: f32,
+    ) -> (bool, (f32, f32), f32) {
+        let alive = boundary.apply(&mut position, &mut heading, DIM);
+        (alive, position, heading)
+    }
+
+    #[test]
+    fn inside_is_untouched() {
+        for boundary in Boundary::ALL {
+            assert_eq!(
+                apply(boundary, (10.0, 20.0), 1.0),
+                (true, (10.0, 20.0), 1.0)
+            );
+        }
+    }
+
+    #[test]
+    fn seam_copies() {
+        assert_eq!(Boundary::Wrap.seam_copies((50.0, 25.0), DIM, 5.0), [(0, 0)]);
+        assert_eq!(
+            Boundary::Wrap.seam_copies((98.0, 25.0), DIM, 5.0),
+            [(0, 0), (-1, 0)]
+        );
+        assert_eq!(
+            Boundary::Wrap.seam_copies((2.0, 48.0), DIM, 5.0),
+            [(0, 0), (0, -1), (1, 0), (1, -1)]
+        );
+        for boundary in [Boundary::Clamp, Boundary::Reflect, Boundary::Lethal] {
+            assert_eq!(boundary.seam_copies((2.0, 48.0), DIM, 5.0), [(0, 0)]);
+        }
+    }
+
+    #[test]
+    fn clamp_and_lethal() {
+        assert_eq!(
+            apply(Boundary::Clamp, (-5.0, 60.0), 1.0),
+            (true, (0.0, 50.0), 1.0)
+        );
+        assert_eq!(
+            apply(Boundary::Lethal, (-5.0, 20.0), 1.0),
+            (false, (0.0, 20.0), 1.0)
+        );
+    }
+
+    #[test]
+    fn wrap() {
+        let (alive, position, _) = apply(Boundary::Wrap, (105.0, -5.0), 1.0);
+        assert!(alive);
+        assert!((position.0 - 5.0).abs() < 1e-4 && (position.1 - 45.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn reflect_mirrors_heading() {
+        let (alive, position, heading) = apply(Boundary::Reflect, (101.0, 20.0), 0.0);
+        assert!(alive);
+        assert_eq!(position, (100.0, 20.0));
+        assert_eq!(heading, PI);
+
+        let (_, position, heading) = apply(Boundary::Reflect, (20.0, -1.0), -PI / 2.0);
+        assert_eq!(position, (20.0, 0.0));
+        assert_eq!(heading, PI / 2.0);
+    }
+
+    #[test]
+    fn delta_crosses_edges_only_when_wrapping() {
+        let (a, b) = ((95.0, 45.0), (5.0, 5.0));
+        let d = Boundary::Clamp.delta(a, b, DIM);
+        assert_eq!((d.x, d.y), (-90.0, -40.0));
+        let d = Boundary::Wrap.delta(a, b, DIM);
+        assert_eq!((d.x, d.y), (10.0, 10.0));
+        let d = Boundary::Wrap.delta(b, a, DIM);
+        assert_eq!((d.x, d.y), (-10.0, -10.0));
+        let d = Boundary::Wrap.delta((10.0, 10.0), (30.0, 20.0), DIM);
+        assert_eq!((d.x, d.y), (20.0, 10.0));
+    }
+
+    fn shapes() -> [Shape; 3] {
+        [
+            Shape::Rect {
+                min: (10.0, 10.0),
+                max: (20.0, 20.0),
+            },
+            Shape::Circle {
+                center: (15.0, 15.0),
+                radius: 5.0,
+            },
+            // diamond around (15, 15)
+            Shape::Polygon {
+                points: vec![(15.0, 10.0), (20.0, 15.0), (15.0, 20.0), (10.0, 15.0)],
+            },
+        ]
+    }
+
+    #[test]
+    fn contains() {
+        for shape in shapes() {
+            assert!(shape.contains((15.0, 15.0)), "{shape:?}");
+            assert!(shape.contains((15.0, 11.0)), "{shape:?}");
+            assert!(!shape.contains((25.0, 15.0)), "{shape:?}");
+            assert!(!shape.contains((15.0, 5.0)), "{shape:?}");
+        }
+        let [rect, circle, polygon] = shapes();
+        // the corner is only inside the rectangle
+        assert!(rect.contains((19.0, 19.0)));
+        assert!(!circle.contains((19.0, 19.0)));
+        assert!(!polygon.contains((19.0, 19.0)));
+    }
+
+    #[test]
+    fn ray() {
+        let origin = Vec2::new(0.0, 15.0);
+        for shape in shapes() {
+            let hit = shape.ray(origin, Vec2::X).unwrap();
+            assert!((hit - 10.0).abs() < 1e-4, "{shape:?} hit at {hit}");
+            assert_eq!(shape.ray(origin, -Vec2::X), None, "{shape:?}");
+            assert_eq!(shape.ray(origin, Vec2::Y), None, "{shape:?}");
+            // starting inside hits right away
+            assert_eq!(
+                shape.ray(Vec2::new(15.0, 15.0), Vec2::X),
+                Some(0.0),
+                "{shape:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn nearest_obstacle() {
+        let [rect, _, _] = shapes();
+        let world = WorldMap {
+            obstacles: vec![
+                Shape::Circle {
+                    center: (50.0, 15.0),
+                    radius: 5.0,
+                },
+                rect,
+            ],
+            terrain: Vec::new(),
+        };
+        assert!(world.blocked((12.0, 12.0)));
+        assert!(!world.blocked((30.0, 15.0)));
+        let hit = world.ray(Vec2::new(0.0, 15.0), Vec2::X).unwrap();
+        assert!((hit - 10.0).abs() < 1e-4);
+    }
+}